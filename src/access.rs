@@ -0,0 +1,185 @@
+use serenity::model::channel::Message;
+use serenity::model::id::{GuildId, UserId};
+use serenity::model::permissions::Permissions;
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+
+use store;
+
+/// The game control commands which not every player is allowed to use
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Start,
+    Stop,
+    Pause,
+    Resume,
+    Hint,
+    /// Skipping a question without a vote
+    ForceSkip,
+    /// Changing a guild's settings
+    Configure,
+    /// Changing who is allowed to do each of these
+    ManageRules,
+}
+
+impl Action {
+    /// Checks if the action controls a running game, which its starter is allowed to do
+    pub fn controls_game(&self) -> bool {
+        match *self {
+            Action::Stop | Action::Pause | Action::Resume | Action::Hint | Action::ForceSkip => true,
+            _ => false,
+        }
+    }
+
+    // What the action does, to finish off "You can't ..."
+    fn describe(&self) -> &'static str {
+        match *self {
+            Action::Start => "start trivia",
+            Action::Stop => "stop this game",
+            Action::Pause => "pause this game",
+            Action::Resume => "resume this game",
+            Action::Hint => "give hints in this game",
+            Action::ForceSkip => "skip questions in this game without a vote",
+            Action::Configure => "change the trivia settings",
+            Action::ManageRules => "change who can control trivia",
+        }
+    }
+}
+
+/// Who may control games in a single guild
+///
+/// The player who started a game, members with the control role, and server admins can always
+/// control it. Everyone can answer, vote to skip and view the scores.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GuildRules {
+    /// The name of the role whose members can control any game. None if no role can.
+    #[serde(default)]
+    pub control_role: Option<String>,
+    /// Whether anyone can start a game, or only members with the control role and admins
+    #[serde(default = "default_open_start")]
+    pub open_start: bool,
+}
+
+impl Default for GuildRules {
+    fn default() -> GuildRules {
+        GuildRules {
+            control_role: None,
+            open_start: true,
+        }
+    }
+}
+
+// Saved rules without open_start let anyone start games
+fn default_open_start() -> bool {
+    true
+}
+
+/// The rules of every guild, kept in sync with a JSON file on disk
+pub struct AccessControl {
+    path: PathBuf,
+    rules: HashMap<u64, GuildRules>,
+}
+
+impl AccessControl {
+    /// Loads the rules stored at `path`
+    ///
+    /// A missing file gives every guild the default rules.
+    pub fn load<P: Into<PathBuf>>(path: P) -> io::Result<AccessControl> {
+        let path = path.into();
+        let rules = store::load_json(&path)?;
+
+        Ok(AccessControl { path, rules })
+    }
+
+    /// Gets the rules of a guild
+    pub fn rules(&self, guild: GuildId) -> GuildRules {
+        self.rules.get(&guild.0).cloned().unwrap_or_default()
+    }
+
+    /// Replaces the rules of a guild and saves them to disk
+    pub fn set_rules(&mut self, guild: GuildId, rules: GuildRules) -> io::Result<()> {
+        self.rules.insert(guild.0, rules);
+        self.save()
+    }
+
+    /// Checks if the author of a message may take an action
+    ///
+    /// `starter` is the player who started the game the action applies to, if there is one.
+    /// Messages outside of a guild can do anything. Returns the reason when the action is denied.
+    pub fn check(&self, message: &Message, action: Action, starter: Option<UserId>) -> Result<(), String> {
+        let guild = match message.guild_id {
+            Some(guild) => guild,
+            None => return Ok(()),
+        };
+        if is_admin(message) {
+            return Ok(());
+        }
+
+        // Only admins can change the rules, so the control role can't hand itself out
+        let rules = self.rules(guild);
+        if action != Action::ManageRules {
+            if let Some(ref role) = rules.control_role {
+                if has_role(message, role) {
+                    return Ok(());
+                }
+            }
+        }
+
+        let allowed = match action {
+            Action::Start => rules.open_start,
+            Action::Configure | Action::ManageRules => false,
+            _ => action.controls_game() && starter == Some(message.author.id),
+        };
+        if allowed {
+            return Ok(());
+        }
+
+        // Say who can, so the player knows who to ask
+        let mut who = Vec::new();
+        if action.controls_game() {
+            who.push(String::from("the player who started it"));
+        }
+        if action != Action::ManageRules {
+            if let Some(ref role) = rules.control_role {
+                who.push(format!("members with the {} role", role));
+            }
+        }
+        who.push(String::from("server admins"));
+
+        Err(format!("You can't {}. Only {} can.", action.describe(), join_names(&who)))
+    }
+
+    // Writes the rules to disk
+    fn save(&self) -> io::Result<()> {
+        store::save_json(&self.path, &self.rules)
+    }
+}
+
+// Checks if the author of a message can manage the guild it was sent in
+// Only works for guilds the bot has cached
+fn is_admin(message: &Message) -> bool {
+    match message.member().map(|member| member.permissions()) {
+        Some(Ok(permissions)) => {
+            permissions.contains(Permissions::ADMINISTRATOR) || permissions.contains(Permissions::MANAGE_GUILD)
+        }
+        _ => false,
+    }
+}
+
+// Checks if the author of a message has a role with the given name, ignoring case
+fn has_role(message: &Message, role: &str) -> bool {
+    match message.member().and_then(|member| member.roles()) {
+        Some(roles) => roles.iter().any(|r| r.name.eq_ignore_ascii_case(role)),
+        None => false,
+    }
+}
+
+// Joins names into a list like "a, b or c"
+fn join_names(names: &[String]) -> String {
+    match names.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} or {}", rest.join(", "), last),
+        Some((last, _)) => last.clone(),
+        None => String::new(),
+    }
+}
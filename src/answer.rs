@@ -0,0 +1,223 @@
+extern crate unicode_normalization;
+
+use self::unicode_normalization::UnicodeNormalization;
+use std::str::FromStr;
+
+use fuzzy::edit_distance;
+
+/// How players answer questions
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AnswerMode {
+    /// Players pick one of the listed options by its label
+    Choice,
+    /// The options are hidden and players type the answer itself
+    Open,
+}
+
+impl FromStr for AnswerMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<AnswerMode, ()> {
+        match s.to_lowercase().as_str() {
+            "choice" => Ok(AnswerMode::Choice),
+            "open" => Ok(AnswerMode::Open),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Checks if a typed guess is close enough to the answer to count
+///
+/// Both are normalized first (see `normalize`). Answers which are numbers or shorter than six
+/// letters have to match exactly, while longer answers allow more typos. `options` are the
+/// question's options, and a guess which is closer to one of the wrong ones than to the answer
+/// doesn't count, so "Austria" isn't taken as a typo of "Australia" when both are listed.
+pub fn is_match(guess: &str, answer: &str, options: &[String]) -> bool {
+    let guess = normalize(guess);
+    let answer = normalize(answer);
+    if guess.is_empty() || answer.is_empty() {
+        return false;
+    }
+    if guess == answer {
+        return true;
+    }
+    if answer.chars().all(|c| c.is_numeric() || c == ' ') {
+        return false;
+    }
+
+    let distance = edit_distance(&guess, &answer);
+    if distance > tolerance(answer.chars().count()) {
+        return false;
+    }
+
+    options
+        .iter()
+        .map(|option| normalize(option))
+        .filter(|option| *option != answer)
+        .all(|option| edit_distance(&guess, &option) >= distance)
+}
+
+/// Folds text down to a form where trivial differences don't matter
+///
+/// Case, accents and punctuation are dropped, a leading "the", "a" or "an" is removed,
+/// thousands separators are taken out of numbers, and number words up to twenty (and the tens
+/// up to ninety) are turned into digits.
+pub fn normalize(text: &str) -> String {
+    // Split accented letters into the letter and its accent, then drop the accents
+    let folded: String = text
+        .nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .collect::<String>()
+        .to_lowercase();
+
+    // Drop commas between digits, so "1,000" and "1000" are the same
+    let chars: Vec<char> = folded.chars().collect();
+    let mut cleaned = String::with_capacity(folded.len());
+    for (i, &c) in chars.iter().enumerate() {
+        let between_digits = i > 0 && i + 1 < chars.len() &&
+            chars[i - 1].is_numeric() && chars[i + 1].is_numeric();
+        if c == ',' && between_digits {
+            continue;
+        }
+        // Any other punctuation separates words
+        cleaned.push(if c.is_alphanumeric() { c } else { ' ' });
+    }
+
+    let mut words: Vec<&str> = cleaned.split_whitespace().collect();
+    if words.len() > 1 && (words[0] == "the" || words[0] == "a" || words[0] == "an") {
+        words.remove(0);
+    }
+
+    words
+        .into_iter()
+        .map(|word| number_word(word).unwrap_or(word))
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+// The number of typos allowed in an answer of the given length
+fn tolerance(length: usize) -> usize {
+    match length {
+        0..=5 => 0,
+        6..=9 => 1,
+        10..=14 => 2,
+        _ => 3,
+    }
+}
+
+// Checks for the combining diacritical marks left behind by decomposing accented letters
+fn is_combining_mark(c: char) -> bool {
+    ('\u{0300}'..='\u{036f}').contains(&c)
+}
+
+// Turns a number word into its digits
+fn number_word(word: &str) -> Option<&'static str> {
+    let digits = match word {
+        "zero" => "0",
+        "one" => "1",
+        "two" => "2",
+        "three" => "3",
+        "four" => "4",
+        "five" => "5",
+        "six" => "6",
+        "seven" => "7",
+        "eight" => "8",
+        "nine" => "9",
+        "ten" => "10",
+        "eleven" => "11",
+        "twelve" => "12",
+        "thirteen" => "13",
+        "fourteen" => "14",
+        "fifteen" => "15",
+        "sixteen" => "16",
+        "seventeen" => "17",
+        "eighteen" => "18",
+        "nineteen" => "19",
+        "twenty" => "20",
+        "thirty" => "30",
+        "forty" => "40",
+        "fifty" => "50",
+        "sixty" => "60",
+        "seventy" => "70",
+        "eighty" => "80",
+        "ninety" => "90",
+        _ => return None,
+    };
+
+    Some(digits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_drops_case_accents_and_punctuation() {
+        assert_eq!(normalize("Beyoncé!"), "beyonce");
+        assert_eq!(normalize("  Rock-and-Roll  "), "rock and roll");
+    }
+
+    #[test]
+    fn normalize_strips_leading_articles() {
+        assert_eq!(normalize("The Beatles"), "beatles");
+        assert_eq!(normalize("An Apple"), "apple");
+        assert_eq!(normalize("a"), "a");
+        assert_eq!(normalize("Theatre"), "theatre");
+    }
+
+    #[test]
+    fn normalize_removes_thousands_separators() {
+        assert_eq!(normalize("1,000,000"), "1000000");
+        assert_eq!(normalize("red, green"), "red green");
+    }
+
+    #[test]
+    fn normalize_turns_number_words_into_digits() {
+        assert_eq!(normalize("Twelve"), "12");
+        assert_eq!(normalize("Seven Samurai"), "7 samurai");
+        assert_eq!(normalize("ninety"), "90");
+    }
+
+    #[test]
+    fn tolerance_grows_with_length() {
+        assert_eq!(tolerance(5), 0);
+        assert_eq!(tolerance(6), 1);
+        assert_eq!(tolerance(10), 2);
+        assert_eq!(tolerance(15), 3);
+    }
+
+    #[test]
+    fn is_match_allows_typos_in_longer_answers() {
+        assert!(is_match("missisippi", "Mississippi", &[]));
+        assert!(is_match("the beatles", "Beatles", &[]));
+        assert!(!is_match("cat", "Car", &[]));
+    }
+
+    #[test]
+    fn is_match_rejects_other_real_answers() {
+        assert!(!is_match("Austria", "Australia", &[]));
+        assert!(!is_match("Iraq", "Iran", &[]));
+        assert!(!is_match("Mali", "Bali", &[]));
+    }
+
+    #[test]
+    fn is_match_rejects_guesses_closer_to_a_wrong_option() {
+        let options = vec![String::from("Colombia"), String::from("Columbia"), String::from("Bolivia")];
+        assert!(is_match("Columbia", "Colombia", &[]));
+        assert!(!is_match("Columbia", "Colombia", &options));
+        assert!(is_match("Colombya", "Colombia", &options));
+    }
+
+    #[test]
+    fn is_match_requires_numbers_to_be_exact() {
+        assert!(is_match("1000", "1,000", &[]));
+        assert!(is_match("twelve", "12", &[]));
+        assert!(!is_match("1001", "1,000", &[]));
+    }
+
+    #[test]
+    fn is_match_rejects_empty_guesses() {
+        assert!(!is_match("", "Paris", &[]));
+        assert!(!is_match("!!", "Paris", &[]));
+    }
+}
@@ -0,0 +1,297 @@
+use serenity::model::id::{ChannelId, GuildId, UserId};
+use std::cmp;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use answer::AnswerMode;
+use hint::HintMode;
+use error::Result;
+use logging;
+use optionset::OptionSet;
+use question::{Question, QuestionType};
+use questionset::QuestionSet;
+use scoring::Scoring;
+use skip::SkipThreshold;
+use source::{Category, QuestionSource};
+use teams::TeamMode;
+
+/// The number of questions kept ready for each set of options games are started with
+pub const POOL_SIZE: usize = 30;
+
+// Questions are pulled in batches small enough that most categories can fill them
+const BATCH_SIZE: u32 = 10;
+// The Open Trivia Database only allows one request every 5 seconds from each IP address
+const BATCH_DELAY_SECS: u64 = 5;
+// How long a pool or the category list is left alone after it couldn't be pulled
+const RETRY_AFTER_SECS: u64 = 60;
+
+// The guild and options a pool's questions were pulled for
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct PoolKey {
+    guild: GuildId,
+    category: String,
+    difficulty: String,
+    question_type: Option<QuestionType>,
+}
+
+impl PoolKey {
+    // Games in direct messages don't get a pool, as every channel has its own session token
+    fn of(options: &OptionSet) -> Option<PoolKey> {
+        Some(PoolKey {
+            guild: options.guild?,
+            category: options.category.clone(),
+            difficulty: options.difficulty.clone(),
+            question_type: options.question_type,
+        })
+    }
+
+    // The options a batch of questions for the pool is pulled with
+    // Batches are pulled for the pool's guild, so they come from the guild's session token and
+    // aren't repeated in its games
+    fn options(&self, number_of_questions: u32) -> OptionSet {
+        OptionSet {
+            number_of_questions,
+            difficulty: self.difficulty.clone(),
+            category: self.category.clone(),
+            question_type: self.question_type,
+            answer_mode: AnswerMode::Choice,
+            scoring: Scoring::Classic,
+            wrong_penalty: 0,
+            skip_threshold: SkipThreshold::Votes(1),
+            team_mode: TeamMode::Off,
+            channel: ChannelId(0),
+            guild: Some(self.guild),
+            started_by: UserId(0),
+            time_limit: None,
+            hint_mode: HintMode::Off,
+            hint_interval: None,
+            prefix: String::new(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Pool {
+    questions: VecDeque<Question>,
+    // Whether a thread is already pulling questions for the pool
+    refilling: bool,
+    // When the pool last couldn't be refilled
+    failed_at: Option<Instant>,
+}
+
+#[derive(Default)]
+struct CategoryList {
+    categories: Option<Vec<Category>>,
+    // Whether a thread is already pulling the list
+    fetching: bool,
+    // When the list last couldn't be pulled
+    failed_at: Option<Instant>,
+}
+
+/// The category list of the source, as far as it has been pulled
+pub enum Categories {
+    Ready(Vec<Category>),
+    /// The list is still being pulled
+    Loading,
+    /// The list couldn't be pulled a short while ago, and won't be asked for again until later
+    Unavailable,
+}
+
+/// Keeps questions ready for the options games in each guild are started with, so they can
+/// start without waiting on the question source
+///
+/// Pools and the category list are filled on their own threads, and nothing holds the bot's
+/// data lock while the source is being waited on.
+pub struct QuestionCache {
+    source: Box<dyn QuestionSource>,
+    pool_size: usize,
+    pools: Mutex<HashMap<PoolKey, Pool>>,
+    categories: Mutex<CategoryList>,
+}
+
+impl QuestionCache {
+    /// Wraps a source, keeping up to `pool_size` questions ready for each set of options
+    ///
+    /// A pool size of 0 keeps no questions ready, so every game's questions are pulled when it
+    /// starts.
+    pub fn new(source: Box<dyn QuestionSource>, pool_size: usize) -> QuestionCache {
+        QuestionCache {
+            source,
+            pool_size,
+            pools: Mutex::new(HashMap::new()),
+            categories: Mutex::new(CategoryList::default()),
+        }
+    }
+
+    /// Takes a game's questions from the pool for its options, if it has enough of them
+    ///
+    /// Never waits on the source.
+    pub fn take(&self, options: &OptionSet) -> Option<QuestionSet> {
+        let wanted = options.number_of_questions as usize;
+        let mut pools = self.pools.lock().expect("Question pool lock poisoned");
+        let pool = pools.get_mut(&PoolKey::of(options)?)?;
+        if wanted == 0 || pool.questions.len() < wanted {
+            return None;
+        }
+
+        let questions = pool.questions.drain(..wanted).collect();
+        info!("questions taken from pool channel={} count={} left={}",
+              options.channel.0, wanted, pool.questions.len());
+
+        Some(QuestionSet::new(questions))
+    }
+
+    /// Pulls a game's questions straight from the source, which can take a while
+    ///
+    /// This shouldn't be called while holding the bot's data lock. If the source can't be
+    /// reached, whatever the pool has for the options is asked instead.
+    pub fn fetch(&self, options: &OptionSet) -> Result<QuestionSet> {
+        let requested = Instant::now();
+        let why = match self.source.get_question_set(options) {
+            Ok(question_set) => {
+                info!("questions fetched channel={} count={} latency_ms={}",
+                      options.channel.0, question_set.len(), logging::millis(requested.elapsed()));
+                return Ok(question_set);
+            }
+            Err(why) => why,
+        };
+        warn!("question fetch failed channel={} latency_ms={} error={:?}",
+              options.channel.0, logging::millis(requested.elapsed()), why);
+
+        let mut pools = self.pools.lock().expect("Question pool lock poisoned");
+        let questions: Vec<Question> = match PoolKey::of(options).and_then(|key| pools.get_mut(&key)) {
+            Some(pool) => {
+                let count = cmp::min(pool.questions.len(), options.number_of_questions as usize);
+                pool.questions.drain(..count).collect()
+            }
+            None => Vec::new(),
+        };
+        if questions.is_empty() {
+            return Err(why);
+        }
+
+        let mut question_set = QuestionSet::new(questions);
+        let adjustment = format!("Couldn't reach the question source, so only the {} questions that were ready will be asked",
+                                 question_set.len());
+        question_set.add_adjustment(adjustment);
+
+        Ok(question_set)
+    }
+
+    /// Tops up the pool for a game's guild and options on another thread
+    ///
+    /// Does nothing outside of guilds, or if the pool is full, is already being refilled, or
+    /// couldn't be refilled a short while ago.
+    pub fn refill(cache: &Arc<QuestionCache>, options: &OptionSet) {
+        if let Some(key) = PoolKey::of(options) {
+            QuestionCache::refill_pool(cache, key);
+        }
+    }
+
+    /// Starts pulling the category list, so the first games after the bot starts don't have to
+    /// wait for it
+    ///
+    /// Pools belong to guilds, so each one is only filled once a game has been started with its
+    /// options.
+    pub fn warm(cache: &Arc<QuestionCache>) {
+        QuestionCache::categories(cache);
+    }
+
+    /// Lists the categories of the source, if the list has been pulled
+    ///
+    /// Never waits on the source. If the list hasn't been pulled yet, it starts being pulled on
+    /// another thread, unless that's already happening or it couldn't be pulled a short while ago.
+    pub fn categories(cache: &Arc<QuestionCache>) -> Categories {
+        {
+            let mut list = cache.categories.lock().expect("Category list lock poisoned");
+            if let Some(ref categories) = list.categories {
+                return Categories::Ready(categories.clone());
+            }
+            if list.fetching {
+                return Categories::Loading;
+            }
+            if list.failed_at.is_some_and(|at| at.elapsed() < Duration::from_secs(RETRY_AFTER_SECS)) {
+                return Categories::Unavailable;
+            }
+            list.fetching = true;
+        }
+
+        let cache = cache.clone();
+        thread::spawn(move || {
+            let result = cache.source.categories();
+            let mut list = cache.categories.lock().expect("Category list lock poisoned");
+            match result {
+                Ok(categories) => list.categories = Some(categories),
+                Err(why) => {
+                    warn!("category list fetch failed error={:?}", why);
+                    list.failed_at = Some(Instant::now());
+                }
+            }
+            list.fetching = false;
+        });
+
+        Categories::Loading
+    }
+
+    // Starts a thread to fill a pool, unless it doesn't need one
+    fn refill_pool(cache: &Arc<QuestionCache>, key: PoolKey) {
+        if cache.pool_size == 0 {
+            return;
+        }
+
+        {
+            let mut pools = cache.pools.lock().expect("Question pool lock poisoned");
+            let pool = pools.entry(key.clone()).or_default();
+            let resting = pool.failed_at.is_some_and(|at| at.elapsed() < Duration::from_secs(RETRY_AFTER_SECS));
+            if pool.refilling || resting || pool.questions.len() >= cache.pool_size {
+                return;
+            }
+            pool.refilling = true;
+        }
+
+        let cache = cache.clone();
+        thread::spawn(move || cache.fill(&key));
+    }
+
+    // Pulls batches of questions until a pool is full or the source can't give any more
+    fn fill(&self, key: &PoolKey) {
+        loop {
+            let requested = Instant::now();
+            let result = self.source.get_question_set(&key.options(BATCH_SIZE));
+
+            let mut pools = self.pools.lock().expect("Question pool lock poisoned");
+            let pool = pools.entry(key.clone()).or_default();
+            let failed = match result {
+                // A batch the source had to change the options for doesn't belong in this pool
+                Ok(batch) => if batch.adjustments().is_empty() && !batch.is_empty() {
+                    pool.questions.extend(batch.into_questions());
+                    debug!("question pool refilled guild={} category={:?} difficulty={:?} size={} latency_ms={}",
+                           key.guild.0, key.category, key.difficulty, pool.questions.len(), logging::millis(requested.elapsed()));
+                    false
+                } else {
+                    info!("question pool can't be filled guild={} category={:?} difficulty={:?} size={}",
+                          key.guild.0, key.category, key.difficulty, pool.questions.len());
+                    true
+                },
+                Err(why) => {
+                    warn!("question pool refill failed guild={} category={:?} difficulty={:?} size={} error={:?}",
+                          key.guild.0, key.category, key.difficulty, pool.questions.len(), why);
+                    true
+                }
+            };
+
+            if failed {
+                pool.failed_at = Some(Instant::now());
+            }
+            if failed || pool.questions.len() >= self.pool_size {
+                pool.refilling = false;
+                return;
+            }
+
+            drop(pools);
+            thread::sleep(Duration::from_secs(BATCH_DELAY_SECS));
+        }
+    }
+}
@@ -1,35 +1,98 @@
+use access::{Action, GuildRules};
+use cache::Categories;
+use help;
+use hint::HintMode;
+use leaderboard::Period;
 use optionset::OptionSet;
+use source::{self, Category, CategoryMatch};
+use startargs::StartArgs;
+use teams::TeamMode;
 use trivia;
+use serenity::model::id::ChannelId;
+use std::time::Duration;
 
 // This command is run when the start command is executed
 command!(trivia_start(context, message, args) {
-    let optionset;
-    let mut number_of_questions;
-    let difficulty;
-
     let mut data = context.data.lock();
     let trivia_manager = data.get_mut::<trivia::TriviaManager>().expect("Error getting TriviaManager from bot data");
 
-    // Have to check if there are zero extra args or Serenity panics on find()
-    if args.is_empty() {
-        number_of_questions = 10;
-        difficulty = String::from("medium");
-    } else {
-        //println!("{:?}", args);
-        number_of_questions = args.find::<u32>().unwrap_or(10);
-        difficulty = args.find::<String>().unwrap_or_else(|_| "medium".to_string());
-        //println!("Num: {}, Diff: {}", number_of_questions, difficulty);
+    if !trivia_manager.allowed(message, Action::Start) {
+        return Ok(());
+    }
+
+    // Options are written like "n=20 diff=hard cat=history", and anything they don't say comes
+    // from the guild's settings
+    let settings = trivia_manager.settings(message.guild_id);
+    let start_args = match StartArgs::parse(args.full(), &settings) {
+        Ok(start_args) => start_args,
+        Err(why) => {
+            let _ = message.channel_id.say(format!("{}\n{}", why, help::usage("tstart", &settings.prefix)));
+            return Ok(());
+        }
+    };
+
+    // Teams are only kept for servers
+    if start_args.team_mode != TeamMode::Off && message.guild_id.is_none() {
+        let _ = message.channel_id.say("Team games can only be played in servers");
+        return Ok(());
     }
 
-    optionset = OptionSet {
-        number_of_questions,
-        difficulty,
-        category: String::from(""),
+    // Look the category up by name, and stop here with a suggestion if it can't be found
+    let mut category = String::new();
+    if !start_args.category.is_empty() {
+        let query = &start_args.category;
+        let categories = match category_list(trivia_manager.categories(), message.channel_id) {
+            Some(categories) => categories,
+            None => return Ok(()),
+        };
+        match source::find_category(&categories, query) {
+            CategoryMatch::Found(found) => category = found.id,
+            CategoryMatch::Ambiguous(candidates) => {
+                let names: Vec<String> = candidates.into_iter().map(|c| c.name).collect();
+                let _ = message.channel_id.say(
+                    format!("\"{}\" matches several categories: {}", query, names.join(", ")));
+                return Ok(());
+            }
+            CategoryMatch::Unknown(closest) => {
+                let _ = message.channel_id.say(match closest {
+                    Some(c) => format!("Unknown category \"{}\". Did you mean \"{}\"?", query, c.name),
+                    None => format!("Unknown category \"{}\". Use {}tcategories to list them.", query, settings.prefix),
+                });
+                return Ok(());
+            }
+        }
+    }
+
+    // Automatic hints are spread evenly over the time limit, or come every 15 seconds when
+    // there is no limit
+    let hint_interval = if start_args.hint_mode == HintMode::Auto {
+        Some(start_args.time_limit.map(|limit| limit / 3).unwrap_or_else(|| Duration::from_secs(15)))
+    } else {
+        None
+    };
+
+    let optionset = OptionSet {
+        number_of_questions: start_args.number_of_questions,
+        difficulty: start_args.difficulty,
+        category,
+        question_type: start_args.question_type,
+        answer_mode: start_args.answer_mode,
+        scoring: start_args.scoring,
+        wrong_penalty: start_args.wrong_penalty,
+        skip_threshold: start_args.skip_threshold,
+        team_mode: start_args.team_mode,
         channel: message.channel_id,
+        guild: message.guild_id,
+        started_by: message.author.id,
+        time_limit: start_args.time_limit,
+        hint_mode: start_args.hint_mode,
+        hint_interval,
+        prefix: settings.prefix.clone(),
     };
 
-    trivia_manager.set_channel(message);
-    trivia_manager.start(&optionset);
+    if let Err(why) = trivia_manager.start(&optionset) {
+        let _ = message.channel_id.say(format!("Couldn't start trivia: {}", why));
+    }
 });
 
 // This command is run when the stop command is executed
@@ -37,14 +100,281 @@ command!(trivia_stop(context, message, _args) {
     let mut data = context.data.lock();
     let trivia_manager = data.get_mut::<trivia::TriviaManager>().expect("Error getting TriviaManager from bot data");
 
-    trivia_manager.set_channel(message);
-    trivia_manager.stop();
+    if !trivia_manager.allowed(message, Action::Stop) {
+        return Ok(());
+    }
+
+    trivia_manager.stop(message.channel_id);
 });
 
 // This command is run when the skip command is executed
-command!(trivia_skip(context, message, _args) {
+command!(trivia_skip(context, message, args) {
     let mut data = context.data.lock();
     let trivia_manager = data.get_mut::<trivia::TriviaManager>().expect("Error getting TriviaManager from bot data");
 
-    trivia_manager.vote_skip(message);
+    // ".tskip force" lets whoever controls the game skip without waiting for votes
+    if args.full().trim().eq_ignore_ascii_case("force") {
+        if !trivia_manager.allowed(message, Action::ForceSkip) {
+            return Ok(());
+        }
+
+        trivia_manager.force_skip(message);
+    } else {
+        trivia_manager.vote_skip(message);
+    }
 });
+
+// This command is run when the pause command is executed
+command!(trivia_pause(context, message, _args) {
+    let mut data = context.data.lock();
+    let trivia_manager = data.get_mut::<trivia::TriviaManager>().expect("Error getting TriviaManager from bot data");
+
+    if !trivia_manager.allowed(message, Action::Pause) {
+        return Ok(());
+    }
+
+    trivia_manager.pause(message);
+});
+
+// This command is run when the resume command is executed
+command!(trivia_resume(context, message, _args) {
+    let mut data = context.data.lock();
+    let trivia_manager = data.get_mut::<trivia::TriviaManager>().expect("Error getting TriviaManager from bot data");
+
+    if !trivia_manager.allowed(message, Action::Resume) {
+        return Ok(());
+    }
+
+    trivia_manager.resume(message);
+});
+
+// This command is run when the hint command is executed
+command!(trivia_hint(context, message, _args) {
+    let mut data = context.data.lock();
+    let trivia_manager = data.get_mut::<trivia::TriviaManager>().expect("Error getting TriviaManager from bot data");
+
+    if !trivia_manager.allowed(message, Action::Hint) {
+        return Ok(());
+    }
+
+    trivia_manager.hint(message);
+});
+
+// This command is run when the scores command is executed
+command!(trivia_scores(context, message, _args) {
+    let data = context.data.lock();
+    let trivia_manager = data.get::<trivia::TriviaManager>().expect("Error getting TriviaManager from bot data");
+
+    trivia_manager.standings(message);
+});
+
+// This command is run when the team command is executed
+command!(trivia_team(context, message, args) {
+    let guild = match message.guild_id {
+        Some(guild) => guild,
+        None => {
+            let _ = message.channel_id.say("Teams are only kept for servers");
+            return Ok(());
+        }
+    };
+
+    let mut data = context.data.lock();
+    let trivia_manager = data.get_mut::<trivia::TriviaManager>().expect("Error getting TriviaManager from bot data");
+
+    let subcommand = args.single::<String>().unwrap_or_default().to_lowercase();
+    let reply = match subcommand.as_str() {
+        "join" if !args.rest().trim().is_empty() => {
+            match trivia_manager.join_team(guild, &message.author, args.rest()) {
+                Ok(team) => format!("{} joined team {}", message.author.name, team),
+                Err(why) => why,
+            }
+        }
+        "leave" => match trivia_manager.leave_team(guild, message.author.id) {
+            Some(team) => format!("{} left team {}", message.author.name, team),
+            None => format!("{} isn't on a team", message.author.name),
+        },
+        "list" => trivia_manager.output_teams(guild),
+        _ => help::usage("tteam", &trivia_manager.settings(Some(guild)).prefix),
+    };
+
+    let _ = message.channel_id.say(reply);
+});
+
+// This command is run when the permissions command is executed
+command!(trivia_permissions(context, message, args) {
+    let guild = match message.guild_id {
+        Some(guild) => guild,
+        None => {
+            let _ = message.channel_id.say("Permissions are only kept for servers");
+            return Ok(());
+        }
+    };
+
+    let mut data = context.data.lock();
+    let trivia_manager = data.get_mut::<trivia::TriviaManager>().expect("Error getting TriviaManager from bot data");
+
+    // With no arguments, just show the current rules
+    let mut rules = trivia_manager.rules(guild);
+    if !args.is_empty() {
+        if !trivia_manager.allowed(message, Action::ManageRules) {
+            return Ok(());
+        }
+
+        let setting = args.single::<String>().unwrap_or_default().to_lowercase();
+        let value = args.rest().trim().to_string();
+        match (setting.as_str(), value.to_lowercase().as_str()) {
+            ("role", "none") => rules.control_role = None,
+            ("role", role) if !role.is_empty() => rules.control_role = Some(value.clone()),
+            ("start", "anyone") => rules.open_start = true,
+            ("start", "controllers") => rules.open_start = false,
+            _ => {
+                let _ = message.channel_id.say(help::usage("tpermissions", &trivia_manager.settings(Some(guild)).prefix));
+                return Ok(());
+            }
+        }
+        trivia_manager.set_rules(guild, rules.clone());
+    }
+
+    let _ = message.channel_id.say(output_rules(&rules));
+});
+
+// This command is run when the config command is executed
+command!(trivia_config(context, message, args) {
+    let guild = match message.guild_id {
+        Some(guild) => guild,
+        None => {
+            let _ = message.channel_id.say("Settings are only kept for servers");
+            return Ok(());
+        }
+    };
+
+    let mut data = context.data.lock();
+    let trivia_manager = data.get_mut::<trivia::TriviaManager>().expect("Error getting TriviaManager from bot data");
+
+    // With no arguments, just show the current settings
+    if args.is_empty() {
+        let _ = message.channel_id.say(trivia_manager.output_settings(guild));
+        return Ok(());
+    }
+    if !trivia_manager.allowed(message, Action::Configure) {
+        return Ok(());
+    }
+
+    let key = args.single::<String>().unwrap_or_default().to_lowercase();
+    let value = args.rest().trim().to_string();
+    let result = if value.is_empty() {
+        Err(help::usage("tconfig", &trivia_manager.settings(Some(guild)).prefix))
+    } else if value.eq_ignore_ascii_case("reset") {
+        trivia_manager.reset_setting(guild, &key)
+    } else {
+        trivia_manager.set_setting(guild, &key, &value)
+    };
+
+    let _ = message.channel_id.say(match result {
+        Ok(()) => trivia_manager.output_settings(guild),
+        Err(why) => why,
+    });
+});
+
+// This command is run when the leaderboard command is executed
+command!(trivia_leaderboard(context, message, args) {
+    let guild = match message.guild_id {
+        Some(guild) => guild,
+        None => {
+            let _ = message.channel_id.say("Leaderboards are only kept for servers");
+            return Ok(());
+        }
+    };
+
+    let data = context.data.lock();
+    let trivia_manager = data.get::<trivia::TriviaManager>().expect("Error getting TriviaManager from bot data");
+
+    // Show the all-time leaderboard unless a period is given
+    let period = if args.is_empty() {
+        Period::AllTime
+    } else {
+        match args.single::<Period>() {
+            Ok(period) => period,
+            Err(_) => {
+                let _ = message.channel_id.say(help::usage("tleaderboard", &trivia_manager.settings(Some(guild)).prefix));
+                return Ok(());
+            }
+        }
+    };
+
+    let _ = message.channel_id.say(trivia_manager.leaderboard(guild, period));
+});
+
+// This command is run when the categories command is executed
+command!(trivia_categories(context, message, _args) {
+    let data = context.data.lock();
+    let trivia_manager = data.get::<trivia::TriviaManager>().expect("Error getting TriviaManager from bot data");
+
+    let categories = match category_list(trivia_manager.categories(), message.channel_id) {
+        Some(categories) => categories,
+        None => return Ok(()),
+    };
+    if categories.is_empty() {
+        let _ = message.channel_id.say("No categories are available right now");
+        return Ok(());
+    }
+
+    let mut output = String::from("Categories:\n");
+    for category in categories {
+        let s = match category.count {
+            Some(count) => format!("{} ({} questions)\n", category.name, count),
+            None => format!("{}\n", category.name),
+        };
+        output.push_str(&s);
+    }
+
+    let _ = message.channel_id.say(output);
+});
+
+// This command is run when the help command is executed
+command!(trivia_help(context, message, args) {
+    // Commands are shown with the prefix that works in this guild
+    let prefix = {
+        let data = context.data.lock();
+        let trivia_manager = data.get::<trivia::TriviaManager>().expect("Error getting TriviaManager from bot data");
+        trivia_manager.settings(message.guild_id).prefix
+    };
+
+    let name = args.full().trim();
+    let result = if name.is_empty() {
+        help::send_overview(message.channel_id, &prefix)
+    } else {
+        match help::find(name, &prefix) {
+            Some(command) => help::send_command(message.channel_id, command, &prefix),
+            None => message.channel_id.say(help::unknown_command(name, &prefix)).map(|_| ()).map_err(From::from),
+        }
+    };
+
+    if let Err(why) = result {
+        error!("couldn't send help channel={} error={:?}", message.channel_id, why);
+    }
+});
+
+// Gets the category list if it's ready, or tells the channel why it isn't
+fn category_list(categories: Categories, channel: ChannelId) -> Option<Vec<Category>> {
+    let why = match categories {
+        Categories::Ready(categories) => return Some(categories),
+        Categories::Loading => "The category list is still loading. Try again in a few seconds.",
+        Categories::Unavailable => "The category list couldn't be loaded. Try again in a minute.",
+    };
+    let _ = channel.say(why);
+
+    None
+}
+
+// Describes who can control games under the given rules
+fn output_rules(rules: &GuildRules) -> String {
+    let controllers = match rules.control_role {
+        Some(ref role) => format!("the player who started it, members with the {} role and server admins", role),
+        None => String::from("the player who started it and server admins"),
+    };
+    let starters = if rules.open_start { "Anyone" } else { "Only members with the control role and server admins" };
+
+    format!("A game can be stopped, paused, resumed or given hints by {}.\n{} can start a game.",
+            controllers, starters)
+}
@@ -0,0 +1,331 @@
+extern crate toml;
+
+use serenity::model::id::GuildId;
+use std::collections::HashMap;
+use std::env;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use skip::SkipThreshold;
+use store;
+
+/// The most questions a game can ask, which is as many as the Open Trivia Database gives at once
+pub const MAX_QUESTIONS: u32 = 50;
+/// The longest time limit a question can have, in seconds
+pub const MAX_TIME_LIMIT: u64 = 600;
+// The longest a command prefix can be
+const MAX_PREFIX_LENGTH: usize = 5;
+
+/// The names of the settings which can be changed, in the order they're listed
+pub const KEYS: [&str; 5] = ["prefix", "questions", "difficulty", "time", "skip"];
+
+/// The settings games in a guild are played with
+#[derive(Clone, Debug)]
+pub struct Settings {
+    /// The prefix commands start with
+    pub prefix: String,
+    /// The number of questions asked when a game doesn't say
+    pub questions: u32,
+    /// The difficulty of questions when a game doesn't say. Empty for any difficulty.
+    pub difficulty: String,
+    /// How long each question stays open when a game doesn't say. None waits forever.
+    pub time_limit: Option<Duration>,
+    /// How many votes it takes to skip a question when a game doesn't say
+    pub skip_threshold: SkipThreshold,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            prefix: String::from("."),
+            questions: 10,
+            difficulty: String::from("medium"),
+            time_limit: None,
+            skip_threshold: SkipThreshold::Votes(3),
+        }
+    }
+}
+
+/// Settings which replace the defaults, where they're given
+///
+/// Every value is checked before it's stored, so stored values can always be applied.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Overrides {
+    prefix: Option<String>,
+    questions: Option<u32>,
+    difficulty: Option<String>,
+    /// In seconds. 0 turns the time limit off.
+    time: Option<u64>,
+    skip: Option<String>,
+}
+
+impl Overrides {
+    /// Checks a setting's value and stores it
+    ///
+    /// Returns why the value can't be used if it's no good.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let value = value.trim();
+        match key {
+            "prefix" => {
+                if value.is_empty() || value.len() > MAX_PREFIX_LENGTH || value.contains(char::is_whitespace) {
+                    return Err(format!("The prefix must be 1 to {} characters with no spaces", MAX_PREFIX_LENGTH));
+                }
+                self.prefix = Some(value.to_string());
+            }
+            "questions" => match value.parse::<u32>() {
+                Ok(questions) if questions > 0 && questions <= MAX_QUESTIONS => self.questions = Some(questions),
+                _ => return Err(format!("The number of questions must be between 1 and {}", MAX_QUESTIONS)),
+            },
+            "difficulty" => match value.to_lowercase().as_str() {
+                "easy" | "medium" | "hard" | "any" => self.difficulty = Some(value.to_lowercase()),
+                _ => return Err(String::from("The difficulty must be easy, medium, hard or any")),
+            },
+            "time" => match value.trim_end_matches('s').parse::<u64>() {
+                Ok(seconds) if seconds <= MAX_TIME_LIMIT => self.time = Some(seconds),
+                _ => return Err(format!("The time limit must be a number of seconds up to {} like 20s, or 0s for none",
+                                        MAX_TIME_LIMIT)),
+            },
+            "skip" => match value.parse::<SkipThreshold>() {
+                Ok(_) => self.skip = Some(value.to_string()),
+                Err(_) => return Err(String::from("The skip votes needed must be a number like 2, or a percentage like 50%")),
+            },
+            _ => return Err(format!("Unknown setting \"{}\". The settings are {}", key, KEYS.join(", "))),
+        }
+
+        Ok(())
+    }
+
+    /// Removes a setting, so the default is used again
+    pub fn reset(&mut self, key: &str) -> Result<(), String> {
+        match key {
+            "prefix" => self.prefix = None,
+            "questions" => self.questions = None,
+            "difficulty" => self.difficulty = None,
+            "time" => self.time = None,
+            "skip" => self.skip = None,
+            _ => return Err(format!("Unknown setting \"{}\". The settings are {}", key, KEYS.join(", "))),
+        }
+
+        Ok(())
+    }
+
+    // Gets a setting written out the way it would be set, if it has been overridden
+    fn value(&self, key: &str) -> Option<String> {
+        match key {
+            "prefix" => self.prefix.clone(),
+            "questions" => self.questions.map(|questions| questions.to_string()),
+            "difficulty" => self.difficulty.clone(),
+            "time" => self.time.map(|seconds| format!("{}s", seconds)),
+            "skip" => self.skip.clone(),
+            _ => None,
+        }
+    }
+
+    // Applies the overrides on top of some settings
+    fn apply(&self, settings: &mut Settings) {
+        if let Some(ref prefix) = self.prefix {
+            settings.prefix = prefix.clone();
+        }
+        if let Some(questions) = self.questions {
+            settings.questions = questions;
+        }
+        if let Some(ref difficulty) = self.difficulty {
+            // "any" difficulty is asked for by leaving the difficulty out
+            settings.difficulty = if difficulty == "any" { String::new() } else { difficulty.clone() };
+        }
+        if let Some(seconds) = self.time {
+            settings.time_limit = if seconds == 0 { None } else { Some(Duration::from_secs(seconds)) };
+        }
+        if let Some(threshold) = self.skip.as_ref().and_then(|skip| skip.parse().ok()) {
+            settings.skip_threshold = threshold;
+        }
+    }
+}
+
+// The layout of the configuration file
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct ConfigFile {
+    /// Seconds a game can stay paused before it's stopped. 0 lets games stay paused forever.
+    pause_limit: Option<u64>,
+    // Read as they're written, so they can be checked the same way as ones set with a command
+    defaults: HashMap<String, toml::Value>,
+}
+
+/// The bot's settings: global defaults from a TOML file and the environment, and the overrides
+/// of each guild, which are kept in sync with a JSON file on disk
+pub struct Config {
+    defaults: Overrides,
+    pause_limit: Option<Duration>,
+    guild_path: PathBuf,
+    guilds: HashMap<u64, Overrides>,
+}
+
+impl Config {
+    /// Loads the defaults from the TOML file at `path`, then the guild overrides at `guild_path`
+    ///
+    /// Either file can be missing. The TOML file can give a `pause_limit` in seconds (0 lets
+    /// games stay paused forever), and a `[defaults]` table with any of the settings in KEYS,
+    /// written the same way as with the config command, eg:
+    ///
+    /// ```toml
+    /// pause_limit = 600
+    ///
+    /// [defaults]
+    /// prefix = "!"
+    /// questions = 15
+    /// difficulty = "any"
+    /// time = "20s"
+    /// skip = 3
+    /// ```
+    ///
+    /// Numbers can be written with or without quotes. Any default can be overridden by an
+    /// environment variable named after it, eg. TRIVIA_PREFIX or TRIVIA_QUESTIONS, and
+    /// TRIVIA_PAUSE_LIMIT sets the pause limit. An invalid setting in the file or the environment
+    /// is an error.
+    pub fn load<P: Into<PathBuf>, G: Into<PathBuf>>(path: P, guild_path: G) -> io::Result<Config> {
+        let file: ConfigFile = match File::open(path.into()) {
+            Ok(mut file) => {
+                let mut contents = String::new();
+                file.read_to_string(&mut contents)?;
+                toml::from_str(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => ConfigFile::default(),
+            Err(e) => return Err(e),
+        };
+
+        if let Some(key) = file.defaults.keys().find(|key| !KEYS.contains(&key.as_str())) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                      format!("{}: Unknown setting. The settings are {}", key, KEYS.join(", "))));
+        }
+
+        // Settings read from the file are checked the same way as ones set with a command
+        let mut defaults = Overrides::default();
+        for key in KEYS.iter() {
+            let value = match env::var(format!("TRIVIA_{}", key.to_uppercase())) {
+                Ok(value) => Some(value),
+                Err(_) => file.defaults.get(*key).map(setting_text),
+            };
+            if let Some(value) = value {
+                defaults.set(key, &value)
+                    .map_err(|why| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", key, why)))?;
+            }
+        }
+
+        let pause_limit = match env::var("TRIVIA_PAUSE_LIMIT") {
+            Ok(seconds) => seconds.parse::<u64>()
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "TRIVIA_PAUSE_LIMIT must be a number of seconds"))?,
+            Err(_) => file.pause_limit.unwrap_or(600),
+        };
+        let pause_limit = if pause_limit == 0 { None } else { Some(Duration::from_secs(pause_limit)) };
+
+        let guild_path = guild_path.into();
+        let guilds = store::load_json(&guild_path)?;
+
+        Ok(Config {
+            defaults,
+            pause_limit,
+            guild_path,
+            guilds,
+        })
+    }
+
+    /// Gets the settings for a guild, or the defaults outside of a guild
+    pub fn settings(&self, guild: Option<GuildId>) -> Settings {
+        let mut settings = Settings::default();
+        self.defaults.apply(&mut settings);
+        if let Some(overrides) = guild.and_then(|guild| self.guilds.get(&guild.0)) {
+            overrides.apply(&mut settings);
+        }
+
+        settings
+    }
+
+    /// Gets the command prefix a guild has chosen, if it has one
+    pub fn guild_prefix(&self, guild: GuildId) -> Option<String> {
+        self.guilds.get(&guild.0).and_then(|overrides| overrides.prefix.clone())
+    }
+
+    /// How long a game can stay paused before it's stopped. None lets games stay paused forever.
+    pub fn pause_limit(&self) -> Option<Duration> {
+        self.pause_limit
+    }
+
+    /// Checks if a guild has overridden a setting
+    pub fn is_overridden(&self, guild: GuildId, key: &str) -> bool {
+        self.guilds.get(&guild.0).is_some_and(|overrides| overrides.value(key).is_some())
+    }
+
+    /// Changes a setting for a guild and saves it to disk
+    ///
+    /// Returns why the value can't be used if it's no good.
+    pub fn set(&mut self, guild: GuildId, key: &str, value: &str) -> Result<(), String> {
+        self.guilds.entry(guild.0).or_default().set(key, value)?;
+        self.save().map_err(|why| format!("Couldn't save the setting: {}", why))
+    }
+
+    /// Puts a guild's setting back to the default and saves it to disk
+    pub fn reset(&mut self, guild: GuildId, key: &str) -> Result<(), String> {
+        self.guilds.entry(guild.0).or_default().reset(key)?;
+        self.save().map_err(|why| format!("Couldn't save the setting: {}", why))
+    }
+
+    // Writes the guild overrides to disk
+    fn save(&self) -> io::Result<()> {
+        store::save_json(&self.guild_path, &self.guilds)
+    }
+}
+
+// Writes a setting from the configuration file out the way it would be given to the config
+// command, so numbers don't need quotes
+fn setting_text(value: &toml::Value) -> String {
+    match *value {
+        toml::Value::String(ref text) => text.clone(),
+        ref other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    // Loads a configuration file with the given contents, and no guild overrides
+    fn load(name: &str, contents: &str) -> io::Result<Config> {
+        let directory = env::temp_dir();
+        let path = directory.join(format!("trivia-config-test-{}.toml", name));
+        fs::write(&path, contents)?;
+        let config = Config::load(&path, directory.join("trivia-config-test-missing.json"));
+        fs::remove_file(&path)?;
+        config
+    }
+
+    #[test]
+    fn file_settings_are_written_like_the_config_command() {
+        let config = load("written", "pause_limit = 0\n\n[defaults]\nquestions = 15\ntime = \"20s\"\nskip = 2\n").unwrap();
+        let settings = config.settings(None);
+        assert_eq!(settings.questions, 15);
+        assert_eq!(settings.time_limit, Some(Duration::from_secs(20)));
+        assert_eq!(settings.skip_threshold, SkipThreshold::Votes(2));
+        assert_eq!(config.pause_limit(), None);
+    }
+
+    #[test]
+    fn numbers_can_be_quoted() {
+        let config = load("quoted", "[defaults]\nquestions = \"15\"\ntime = 20\nskip = \"50%\"\n").unwrap();
+        let settings = config.settings(None);
+        assert_eq!(settings.questions, 15);
+        assert_eq!(settings.time_limit, Some(Duration::from_secs(20)));
+        assert_eq!(settings.skip_threshold, SkipThreshold::Percent(50));
+    }
+
+    #[test]
+    fn bad_file_settings_are_errors() {
+        assert!(load("unknown", "[defaults]\ncolour = \"red\"\n").is_err());
+        assert!(load("invalid", "[defaults]\nquestions = 0\n").is_err());
+        assert!(load("too_long", &format!("[defaults]\ntime = {}\n", MAX_TIME_LIMIT + 1)).is_err());
+    }
+}
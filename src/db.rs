@@ -2,17 +2,74 @@ extern crate reqwest;
 extern crate serde_json;
 extern crate url;
 
+use error::{Error, Result};
+use logging;
 use optionset::OptionSet;
 use question::Question;
 use questionset::QuestionSet;
+use source::{Category, QuestionSource};
+use std::cmp;
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use store;
 use url::Url;
 
+// The database only allows one request every 5 seconds from each IP address
+const RETRY_DELAY_SECS: u64 = 5;
+
+// When there aren't enough questions, the number asked for is halved down to this before
+// anything else is relaxed
+const FALLBACK_QUESTIONS: u32 = 5;
+
+/// The response codes the Open Trivia Database answers question requests with
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ResponseCode {
+    /// 0: The questions were returned
+    Success,
+    /// 1: There aren't enough questions to answer the query
+    NoResults,
+    /// 2: One of the parameters was invalid
+    InvalidParameter,
+    /// 3: The session token doesn't exist
+    TokenNotFound,
+    /// 4: The session token has returned every question for the query
+    TokenEmpty,
+    /// 5: Too many requests have been made in a short time
+    RateLimit,
+    /// Any code this client doesn't know about
+    Unknown(i32),
+}
+
+impl ResponseCode {
+    fn from_code(code: i32) -> ResponseCode {
+        match code {
+            0 => ResponseCode::Success,
+            1 => ResponseCode::NoResults,
+            2 => ResponseCode::InvalidParameter,
+            3 => ResponseCode::TokenNotFound,
+            4 => ResponseCode::TokenEmpty,
+            5 => ResponseCode::RateLimit,
+            code => ResponseCode::Unknown(code),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 struct EntrySet {
     response_code: i32,
     results: Vec<Entry>,
 }
 
+impl EntrySet {
+    fn response_code(&self) -> ResponseCode {
+        ResponseCode::from_code(self.response_code)
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 struct Entry {
     category: String,
@@ -22,23 +79,265 @@ struct Entry {
     incorrect_answers: Vec<String>,
 }
 
-/// Produces a QuestionSet based on the given OptionSet.
-pub fn get_question_set(options: &OptionSet) -> QuestionSet {
-    // Grab the number of questions before we consume the optionset
-    let number_of_questions = options.number_of_questions;
+#[derive(Deserialize)]
+struct TokenResponse {
+    response_code: i32,
+    // Error responses don't include a token
+    #[serde(default)]
+    token: String,
+}
+
+#[derive(Deserialize)]
+struct CategoryList {
+    trivia_categories: Vec<CategoryEntry>,
+}
+
+#[derive(Deserialize)]
+struct CategoryEntry {
+    id: u32,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct GlobalCount {
+    categories: HashMap<String, CategoryCount>,
+}
+
+#[derive(Deserialize)]
+struct CategoryCount {
+    total_num_of_verified_questions: u32,
+}
+
+/// Pulls questions from the Open Trivia Database at https://opentdb.com
+///
+/// Every guild gets its own session token, so the database won't repeat questions in a guild
+/// until it has run out of new ones. Games in direct messages get a token per channel.
+pub struct OpenTdb {
+    tokens: Mutex<TokenStore>,
+}
+
+impl OpenTdb {
+    /// Creates a client which keeps its session tokens in the JSON file at `token_path`
+    ///
+    /// A missing file starts with no tokens, and will be created when the first one is requested.
+    pub fn new<P: Into<PathBuf>>(token_path: P) -> io::Result<OpenTdb> {
+        Ok(OpenTdb {
+            tokens: Mutex::new(TokenStore::load(token_path.into())?),
+        })
+    }
+
+    // Produces a QuestionSet based on the given OptionSet.
+    //
+    // Questions are pulled with the session token for the game's guild, which is replaced if
+    // the database no longer knows it and reset once it has given out every question.
+    //
+    // If the database doesn't have enough questions for the options, they are relaxed until it
+    // does: first by asking for fewer questions, then by allowing any difficulty, then by
+    // allowing any category. Each change made is noted on the returned QuestionSet.
+    fn fetch_question_set(&self, options: &OptionSet) -> Result<QuestionSet> {
+        let key = options.guild.map(|guild| guild.0).unwrap_or(options.channel.0);
+        let mut token = self.token(key)?;
+
+        let mut relaxed = options.clone();
+        let mut renewed = false;
+        let mut reset = false;
+        let mut limited = false;
+        let res = loop {
+            let requested = Instant::now();
+            let res = get_entries(&relaxed, Some(&token))?;
+            info!("opentdb request key={} questions={} difficulty={:?} category={:?} response={:?} latency_ms={}",
+                  key, relaxed.number_of_questions, relaxed.difficulty, relaxed.category,
+                  res.response_code(), logging::millis(requested.elapsed()));
+            match res.response_code() {
+                ResponseCode::Success => break res,
+                // Tokens expire after a few hours without use
+                ResponseCode::TokenNotFound if !renewed => {
+                    warn!("opentdb session token expired key={}", key);
+                    token = self.renew_token(key)?;
+                    renewed = true;
+                }
+                ResponseCode::TokenEmpty if !reset => {
+                    info!("opentdb session token used up key={}", key);
+                    reset_token(&token)?;
+                    reset = true;
+                }
+                // Question pools are refilled alongside games starting, so requests can land
+                // too close together
+                ResponseCode::RateLimit if !limited => {
+                    warn!("opentdb rate limited key={}", key);
+                    limited = true;
+                }
+                ResponseCode::NoResults | ResponseCode::TokenEmpty => {
+                    if !relax(&mut relaxed, options.number_of_questions) {
+                        return Err(Error::Api(res.response_code()));
+                    }
+                }
+                code => return Err(Error::Api(code)),
+            }
+            thread::sleep(Duration::from_secs(RETRY_DELAY_SECS));
+        };
+
+        let mut question_set = build_question_set(&res)?;
+        if reset {
+            question_set.add_adjustment(String::from("Every question for those options had been asked, so questions may repeat"));
+        }
+        for adjustment in describe_changes(options, &relaxed) {
+            question_set.add_adjustment(adjustment);
+        }
+
+        Ok(question_set)
+    }
+
+    // Gets the session token for a key, requesting one if the key doesn't have one yet
+    // The token store is only locked to read and store tokens, never while waiting on the database
+    fn token(&self, key: u64) -> Result<String> {
+        let token = self.tokens.lock().expect("Token store lock poisoned").get(key);
+        match token {
+            Some(token) => Ok(token),
+            None => self.renew_token(key),
+        }
+    }
+
+    // Requests a new session token for a key, replacing any old one
+    fn renew_token(&self, key: u64) -> Result<String> {
+        let token = request_token()?;
+        self.tokens.lock().expect("Token store lock poisoned").insert(key, token.clone())?;
+
+        Ok(token)
+    }
+}
+
+impl QuestionSource for OpenTdb {
+    fn get_question_set(&self, options: &OptionSet) -> Result<QuestionSet> {
+        self.fetch_question_set(options)
+    }
 
+    fn categories(&self) -> Result<Vec<Category>> {
+        get_categories()
+    }
+}
+
+// Pulls the list of categories along with how many questions each one has
+fn get_categories() -> Result<Vec<Category>> {
+    let list: CategoryList = reqwest::get("https://opentdb.com/api_category.php")?.json()?;
+    let counts: GlobalCount = reqwest::get("https://opentdb.com/api_count_global.php")?.json()?;
+
+    let categories = list.trivia_categories
+        .into_iter()
+        .map(|entry| {
+            let id = entry.id.to_string();
+            let count = counts.categories.get(&id).map(|c| c.total_num_of_verified_questions);
+            Category {
+                id,
+                name: entry.name,
+                count,
+            }
+        })
+        .collect();
+
+    Ok(categories)
+}
+
+// Session tokens by guild (or channel), kept in sync with a JSON file on disk
+struct TokenStore {
+    path: PathBuf,
+    tokens: HashMap<u64, String>,
+}
+
+impl TokenStore {
+    fn load(path: PathBuf) -> io::Result<TokenStore> {
+        let tokens = store::load_json(&path)?;
+
+        Ok(TokenStore { path, tokens })
+    }
+
+    // Gets the token for a key, if it has one
+    fn get(&self, key: u64) -> Option<String> {
+        self.tokens.get(&key).cloned()
+    }
+
+    // Stores the token for a key, replacing any old one
+    fn insert(&mut self, key: u64, token: String) -> io::Result<()> {
+        self.tokens.insert(key, token);
+        self.save()
+    }
+
+    // Writes the tokens to disk
+    fn save(&self) -> io::Result<()> {
+        store::save_json(&self.path, &self.tokens)
+    }
+}
+
+// Requests a brand new session token
+fn request_token() -> Result<String> {
+    let res: TokenResponse = reqwest::get("https://opentdb.com/api_token.php?command=request")?.json()?;
+    match ResponseCode::from_code(res.response_code) {
+        ResponseCode::Success => Ok(res.token),
+        code => Err(Error::Api(code)),
+    }
+}
+
+// Resets a session token so it can give out every question again
+fn reset_token(token: &str) -> Result<()> {
+    let url = Url::parse_with_params("https://opentdb.com/api_token.php",
+                                     &[("command", "reset"), ("token", token)])?;
+    let res: TokenResponse = reqwest::get(url)?.json()?;
+    match ResponseCode::from_code(res.response_code) {
+        ResponseCode::Success => Ok(()),
+        code => Err(Error::Api(code)),
+    }
+}
+
+// Pulls questions for the given options, without checking the response code
+fn get_entries(options: &OptionSet, token: Option<&str>) -> Result<EntrySet> {
     // Pull our trivia data as JSON
-    let url = compose_url(options).expect("Error creating db URL");
-    let json = get_json(url).expect("Error pulling JSON data");
+    let url = compose_url(options, token)?;
+    let json = get_json(url)?;
     // println!("JSON: {}\n", json);
 
     // Create our raw dataset from the JSON
-    let res: EntrySet = serde_json::from_str(&json).expect("Error converting JSON to questionset");
+    Ok(serde_json::from_str(&json)?)
+}
+
+// Loosens the options after the database said it doesn't have enough questions for them.
+// `requested` is the number of questions that was originally asked for.
+// Returns false once there is nothing left to loosen.
+fn relax(options: &mut OptionSet, requested: u32) -> bool {
+    let floor = cmp::min(requested, FALLBACK_QUESTIONS);
+    if options.number_of_questions > floor {
+        options.number_of_questions = cmp::max(options.number_of_questions / 2, floor);
+    } else if !options.difficulty.is_empty() {
+        options.difficulty = String::new();
+        options.number_of_questions = requested;
+    } else if !options.category.is_empty() {
+        options.category = String::new();
+        options.number_of_questions = requested;
+    } else {
+        return false;
+    }
+
+    true
+}
 
-    if res.response_code == 2 {
-        panic!("Response from DB contains an error in it's response code");
+// Describes each way the relaxed options differ from the ones that were asked for
+fn describe_changes(original: &OptionSet, relaxed: &OptionSet) -> Vec<String> {
+    let mut changes = Vec::new();
+    if relaxed.category != original.category {
+        changes.push(String::from("Not enough questions in that category, so questions can come from any category"));
+    }
+    if relaxed.difficulty != original.difficulty {
+        changes.push(format!("Not enough {} questions, so questions can be of any difficulty", original.difficulty));
+    }
+    if relaxed.number_of_questions != original.number_of_questions {
+        changes.push(format!("Not enough questions for {}, so only {} will be asked",
+                             original.number_of_questions, relaxed.number_of_questions));
     }
 
+    changes
+}
+
+// Turns the raw dataset into a QuestionSet
+fn build_question_set(res: &EntrySet) -> Result<QuestionSet> {
     // Create an empty questionset
     let mut questions: Vec<Question> = Vec::new();
 
@@ -50,34 +349,38 @@ pub fn get_question_set(options: &OptionSet) -> QuestionSet {
                                      result.incorrect_answers.clone(), 
                                      result.category.clone(), 
                                      result.difficulty.clone(), 
-                                     false);
+                                     false)?;
         // Add the completed question object to the questions list
         questions.push(question);
     }
 
     // Return the new questionset
-    QuestionSet::new(questions, number_of_questions)
+    Ok(QuestionSet::new(questions))
 }
 
 // Requests JSON from the given URL and returns it as a String
-fn get_json(url: Url) -> Result<String, reqwest::Error> {
+fn get_json(url: Url) -> Result<String> {
     let json = reqwest::get(url)?.text()?;
     Ok(json)
 }
 
 // Composes a trivia request URL based on parameters.
-fn compose_url(options: &OptionSet) -> Result<Url, url::ParseError> {
+fn compose_url(options: &OptionSet, token: Option<&str>) -> Result<Url> {
     let num = options.number_of_questions.to_string();
-    let url = Url::parse_with_params(
+    let mut url = Url::parse_with_params(
         "https://opentdb.com/api.php",
         &[
             ("amount", num),
-            ("type", "multiple".to_string()),
+            ("type", options.question_type.map(|t| t.name()).unwrap_or("").to_string()),
             ("difficulty", options.difficulty.clone()),
             ("category", options.category.clone()),
         ],
     )?;
 
+    if let Some(token) = token {
+        url.query_pairs_mut().append_pair("token", token);
+    }
+
     // println!("URL: {}", url);
     Ok(url)
 }
@@ -0,0 +1,98 @@
+extern crate reqwest;
+extern crate serde_json;
+extern crate url;
+
+use db::ResponseCode;
+use serenity;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+
+/// Everything that can go wrong while fetching questions or running a game
+#[derive(Debug)]
+pub enum Error {
+    /// The request to the question database failed
+    Http(reqwest::Error),
+    /// The question database sent back JSON we couldn't make sense of
+    Json(serde_json::Error),
+    /// A request URL couldn't be built
+    Url(url::ParseError),
+    /// Reading or writing a local file failed
+    Io(io::Error),
+    /// The question database answered with an error response code
+    Api(ResponseCode),
+    /// A piece of question text couldn't be HTML decoded
+    Decode(String),
+    /// No questions matched the options a game was started with
+    NoQuestions,
+    /// Sending something to Discord failed
+    Discord(serenity::Error),
+}
+
+/// A Result whose error is this crate's Error
+pub type Result<T> = ::std::result::Result<T, Error>;
+
+// These messages are shown to players, so they are kept short and friendly.
+// The details are available through Debug.
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            Error::Http(_) => write!(f, "Couldn't reach the trivia database"),
+            Error::Json(_) => write!(f, "The trivia database sent back something unexpected"),
+            Error::Url(_) => write!(f, "Couldn't build a request for the trivia database"),
+            Error::Io(_) => write!(f, "Couldn't read or write a file"),
+            Error::Api(ResponseCode::NoResults) => write!(f, "The trivia database doesn't have enough questions for those options"),
+            Error::Api(ResponseCode::InvalidParameter) => write!(f, "The trivia database didn't understand those options"),
+            Error::Api(ResponseCode::TokenNotFound) => write!(f, "The trivia database didn't recognise this server's session"),
+            Error::Api(ResponseCode::TokenEmpty) => write!(f, "Every question for those options has already been asked"),
+            Error::Api(ResponseCode::RateLimit) => write!(f, "The trivia database is busy, try again in a few seconds"),
+            Error::Api(code) => write!(f, "The trivia database returned an error ({:?})", code),
+            Error::Decode(ref text) => write!(f, "Couldn't read a question: {}", text),
+            Error::NoQuestions => write!(f, "No questions matched those options"),
+            Error::Discord(_) => write!(f, "Couldn't send a message to Discord"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Http(ref e) => Some(e),
+            Error::Json(ref e) => Some(e),
+            Error::Url(ref e) => Some(e),
+            Error::Io(ref e) => Some(e),
+            Error::Discord(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Error {
+        Error::Http(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Error {
+        Error::Json(e)
+    }
+}
+
+impl From<url::ParseError> for Error {
+    fn from(e: url::ParseError) -> Error {
+        Error::Url(e)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+impl From<serenity::Error> for Error {
+    fn from(e: serenity::Error) -> Error {
+        Error::Discord(e)
+    }
+}
@@ -0,0 +1,23 @@
+/// Counts the number of single character insertions, deletions and substitutions needed to turn
+/// one string into the other (the Levenshtein distance)
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // Only the previous row of the distance table is needed to build the next one
+    let mut previous: Vec<usize> = (0..b.len() + 1).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = if ca == cb { previous[j] } else { previous[j] + 1 };
+            current[j + 1] = substitution
+                .min(previous[j + 1] + 1)
+                .min(current[j] + 1);
+        }
+        ::std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
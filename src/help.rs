@@ -0,0 +1,251 @@
+use serenity::builder::CreateEmbed;
+use serenity::client::Context;
+use serenity::framework::standard::{Args, Command, StandardFramework};
+use serenity::model::channel::Message;
+use serenity::model::id::ChannelId;
+use serenity::utils::Colour;
+
+use commands;
+use error::Result;
+use fuzzy::edit_distance;
+use present::can_embed;
+
+/// A command the bot registers, with what it does and how it's used
+pub struct CommandHelp {
+    /// The name the command is registered under, without the prefix
+    pub name: &'static str,
+    /// The arguments it takes, eg. "[all|month|week]". Empty if it takes none.
+    pub args: &'static str,
+    pub description: &'static str,
+    /// Example arguments, which are shown after the command
+    pub examples: &'static [&'static str],
+    /// What runs the command
+    pub handler: &'static dyn Command,
+}
+
+/// Every command the bot registers, in the order they're listed by the help command
+pub const COMMANDS: [CommandHelp; 13] = [
+    CommandHelp {
+        name: "tstart",
+        args: "[n=<1-50>] [diff=easy|medium|hard|any] [cat=<category>] [type=multiple|boolean|mixed] \
+               [time=<seconds>s] [mode=choice|open] [scoring=classic|weighted|speed|streak] \
+               [teams=solo|teams|roleteams] [skip=<votes>|<percent>%] [penalty=<points>] [hints=off|manual|auto]",
+        description: "Starts a game in this channel. Options can be given in any order, and anything left out \
+                      comes from the server's settings.",
+        examples: &["", "n=20 diff=hard cat=history", "cat=video games type=boolean time=20s", "mode=open teams=teams"],
+        handler: &commands::trivia_start,
+    },
+    CommandHelp {
+        name: "tstop",
+        args: "",
+        description: "Stops the game in this channel and posts the final scores.",
+        examples: &[],
+        handler: &commands::trivia_stop,
+    },
+    CommandHelp {
+        name: "tskip",
+        args: "[force]",
+        description: "Votes to skip the current question. Whoever controls the game can skip it straight away \
+                      with force.",
+        examples: &["", "force"],
+        handler: &commands::trivia_skip,
+    },
+    CommandHelp {
+        name: "tpause",
+        args: "",
+        description: "Pauses the game in this channel. Games paused for too long are stopped.",
+        examples: &[],
+        handler: &commands::trivia_pause,
+    },
+    CommandHelp {
+        name: "tresume",
+        args: "",
+        description: "Resumes a paused game, asking the current question again.",
+        examples: &[],
+        handler: &commands::trivia_resume,
+    },
+    CommandHelp {
+        name: "thint",
+        args: "",
+        description: "Gives a hint for the current question, which makes it worth fewer points.",
+        examples: &[],
+        handler: &commands::trivia_hint,
+    },
+    CommandHelp {
+        name: "tscores",
+        args: "",
+        description: "Shows the scores of the game in this channel.",
+        examples: &[],
+        handler: &commands::trivia_scores,
+    },
+    CommandHelp {
+        name: "tteam",
+        args: "join <name> | leave | list",
+        description: "Joins, leaves or lists the teams used by games started with teams=teams.",
+        examples: &["join Red", "leave", "list"],
+        handler: &commands::trivia_team,
+    },
+    CommandHelp {
+        name: "tleaderboard",
+        args: "[all|month|week]",
+        description: "Shows the server's leaderboard, for all time unless a period is given.",
+        examples: &["", "week"],
+        handler: &commands::trivia_leaderboard,
+    },
+    CommandHelp {
+        name: "tcategories",
+        args: "",
+        description: "Lists the categories questions can be asked from.",
+        examples: &[],
+        handler: &commands::trivia_categories,
+    },
+    CommandHelp {
+        name: "tconfig",
+        args: "[<setting> <value>|reset]",
+        description: "Shows the server's settings, or changes one. The settings are prefix, questions, \
+                      difficulty, time and skip.",
+        examples: &["", "questions 15", "time 20s", "skip reset"],
+        handler: &commands::trivia_config,
+    },
+    CommandHelp {
+        name: "tpermissions",
+        args: "[role <name>|none] or [start anyone|controllers]",
+        description: "Shows who can control games, or changes it. Only server admins can change it.",
+        examples: &["", "role Quizmaster", "start controllers"],
+        handler: &commands::trivia_permissions,
+    },
+    CommandHelp {
+        name: "thelp",
+        args: "[<command>]",
+        description: "Lists the commands, or explains one of them.",
+        examples: &["", "tstart"],
+        handler: &commands::trivia_help,
+    },
+];
+
+// How players answer, which isn't a command of its own
+const ANSWERING: &str = "Answer a question by sending the letter of your pick, eg. B, or T or F for true or false. \
+                         In games started with mode=open, type the answer instead. Only your first answer counts.";
+
+/// Registers every command in COMMANDS with the framework, so the help always lists exactly the
+/// commands the bot answers to
+pub fn register(framework: StandardFramework) -> StandardFramework {
+    COMMANDS.iter().fold(framework, |framework, command| {
+        framework.command(command.name, |c| c
+            .desc(command.description)
+            .usage(command.args)
+            .cmd(move |context: &mut Context, message: &Message, args: Args| {
+                command.handler.execute(context, message, args)
+            }))
+    })
+}
+
+/// Finds the help for a command, with or without its prefix
+pub fn find(name: &str, prefix: &str) -> Option<&'static CommandHelp> {
+    let name = name.trim().to_lowercase();
+    let name = if name.starts_with(prefix) { &name[prefix.len()..] } else { &name[..] };
+    COMMANDS.iter().find(|command| command.name == name)
+}
+
+/// Gets the usage line for a command written with `prefix`, eg. "Usage: .tteam join <name> | leave | list"
+///
+/// Panics if the command isn't in COMMANDS.
+pub fn usage(name: &str, prefix: &str) -> String {
+    let command = find(name, prefix).expect("Usage asked for a command with no help");
+    format!("Usage: {}", syntax(command, prefix))
+}
+
+/// Posts a list of every command to a channel
+pub fn send_overview(channel: ChannelId, prefix: &str) -> Result<()> {
+    if can_embed(channel) {
+        channel.send_message(|m| m.embed(|e| overview_embed(e, prefix)))?;
+    } else {
+        channel.say(overview_text(prefix))?;
+    }
+
+    Ok(())
+}
+
+/// Posts the details of one command to a channel
+pub fn send_command(channel: ChannelId, command: &CommandHelp, prefix: &str) -> Result<()> {
+    if can_embed(channel) {
+        channel.send_message(|m| m.embed(|e| command_embed(e, command, prefix)))?;
+    } else {
+        channel.say(command_text(command, prefix))?;
+    }
+
+    Ok(())
+}
+
+/// Describes a command which couldn't be found, suggesting the closest one
+pub fn unknown_command(name: &str, prefix: &str) -> String {
+    let name = name.trim().to_lowercase();
+    let closest = COMMANDS.iter().min_by_key(|command| edit_distance(&name, command.name));
+    match closest {
+        Some(closest) if edit_distance(&name, closest.name) <= 2 => {
+            format!("Unknown command \"{}\". Did you mean {}{}?", name, prefix, closest.name)
+        }
+        _ => format!("Unknown command \"{}\". Use {}thelp to list them.", name, prefix),
+    }
+}
+
+// The command written out with its arguments
+fn syntax(command: &CommandHelp, prefix: &str) -> String {
+    if command.args.is_empty() {
+        format!("{}{}", prefix, command.name)
+    } else {
+        format!("{}{} {}", prefix, command.name, command.args)
+    }
+}
+
+// The examples written out in full, one to a line
+fn examples(command: &CommandHelp, prefix: &str) -> String {
+    command.examples
+        .iter()
+        .map(|args| format!("{}{} {}", prefix, command.name, args).trim_end().to_string())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn overview_embed(e: CreateEmbed, prefix: &str) -> CreateEmbed {
+    let mut e = e.title("Trivia commands").colour(Colour::BLURPLE);
+    for command in COMMANDS.iter() {
+        e = e.field(format!("{}{}", prefix, command.name), command.description, false);
+    }
+
+    e.field("Answering", ANSWERING, false)
+        .footer(|f| f.text(format!("Use {}thelp <command> for its options and examples", prefix)))
+}
+
+fn overview_text(prefix: &str) -> String {
+    let mut output = String::from("**Trivia commands**\n");
+    for command in COMMANDS.iter() {
+        output.push_str(&format!("`{}{}` {}\n", prefix, command.name, command.description));
+    }
+    output.push_str(&format!("\n{}\nUse {}thelp <command> for its options and examples", ANSWERING, prefix));
+
+    output
+}
+
+fn command_embed(e: CreateEmbed, command: &CommandHelp, prefix: &str) -> CreateEmbed {
+    let mut e = e
+        .title(format!("{}{}", prefix, command.name))
+        .colour(Colour::BLURPLE)
+        .description(command.description)
+        .field("Usage", format!("`{}`", syntax(command, prefix)), false);
+    if !command.examples.is_empty() {
+        e = e.field("Examples", format!("```{}```", examples(command, prefix)), false);
+    }
+
+    e
+}
+
+fn command_text(command: &CommandHelp, prefix: &str) -> String {
+    let mut output = format!("**{}{}** {}\nUsage: `{}`", prefix, command.name, command.description,
+                             syntax(command, prefix));
+    if !command.examples.is_empty() {
+        output.push_str(&format!("\nExamples:```{}```", examples(command, prefix)));
+    }
+
+    output
+}
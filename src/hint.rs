@@ -0,0 +1,129 @@
+use rand::{thread_rng, Rng};
+use std::str::FromStr;
+
+use answer::AnswerMode;
+use question::{Question, QuestionType};
+
+// Open answers are revealed over this many hints
+const REVEAL_STAGES: usize = 2;
+
+/// Whether a game gives hints, and when
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HintMode {
+    /// No hints are given, and answers aren't worth more for leaving hints unused
+    Off,
+    /// Hints are given when a player asks for one
+    Manual,
+    /// Hints are also given on a schedule while a question is open
+    Auto,
+}
+
+impl FromStr for HintMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<HintMode, ()> {
+        match s.to_lowercase().as_str() {
+            "off" => Ok(HintMode::Off),
+            "manual" => Ok(HintMode::Manual),
+            "auto" => Ok(HintMode::Auto),
+            _ => Err(()),
+        }
+    }
+}
+
+// The kinds of hint a question can get
+enum HintKind {
+    // Strike out the wrong options at these indexes, one more with each hint
+    Eliminate(Vec<usize>),
+    // Reveal more of the letters of the answer with each hint
+    Reveal,
+}
+
+/// Tracks the hints given out for a single question
+///
+/// When players pick from the options, each hint eliminates one more wrong answer. At least one
+/// wrong answer is always left standing, so a question with four options has two hints.
+///
+/// When players type their answers, each hint reveals more letters of the answer, starting
+/// from the front of each word. True/False questions get no hints at all.
+pub struct Hints {
+    kind: HintKind,
+    stages: usize,
+    used: usize,
+}
+
+impl Hints {
+    /// Prepares the hints for a question asked in the given answer mode
+    pub fn new(question: &Question, answer_mode: AnswerMode) -> Hints {
+        if answer_mode == AnswerMode::Open {
+            let stages = if question.question_type == QuestionType::Boolean { 0 } else { REVEAL_STAGES };
+            return Hints {
+                kind: HintKind::Reveal,
+                stages,
+                used: 0,
+            };
+        }
+
+        // Pick a random order to eliminate wrong answers in
+        let mut elimination_order = question.wrong_options();
+        thread_rng().shuffle(&mut elimination_order);
+        // Always leave one wrong answer so the hints never give the answer away outright
+        elimination_order.pop();
+
+        Hints {
+            stages: elimination_order.len(),
+            kind: HintKind::Eliminate(elimination_order),
+            used: 0,
+        }
+    }
+
+    /// Gives out the next hint, either as a formatted list of the remaining options or as
+    /// the partly revealed answer
+    ///
+    /// Returns None once every hint for the question has been used
+    pub fn next_hint(&mut self, question: &Question) -> Option<String> {
+        if self.remaining() == 0 {
+            return None;
+        }
+
+        self.used += 1;
+        match self.kind {
+            HintKind::Eliminate(ref order) => Some(question.options_prompt(&order[..self.used])),
+            HintKind::Reveal => Some(format!("`{}`", reveal(&question.answer, self.used, self.stages))),
+        }
+    }
+
+    /// The number of hints given so far
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    /// The number of hints that can still be given
+    pub fn remaining(&self) -> usize {
+        self.stages - self.used
+    }
+}
+
+// Masks the letters of an answer with underscores, except for the start of each word.
+// At `stage` out of `stages`, each word shows stage / (stages + 1) of its letters (rounded up),
+// so even the last hint keeps part of longer words hidden. Punctuation is always shown.
+fn reveal(answer: &str, stage: usize, stages: usize) -> String {
+    answer
+        .split(' ')
+        .map(|word| {
+            let letters = word.chars().filter(|c| c.is_alphanumeric()).count();
+            let shown = (letters * stage + stages) / (stages + 1);
+            let mut seen = 0;
+            word.chars()
+                .map(|c| {
+                    if !c.is_alphanumeric() {
+                        return c;
+                    }
+                    seen += 1;
+                    if seen <= shown { c } else { '_' }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
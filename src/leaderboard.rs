@@ -0,0 +1,140 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use serenity::model::id::{GuildId, UserId};
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use store;
+
+/// The stretch of time a leaderboard covers
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Period {
+    AllTime,
+    /// The current calendar month (UTC)
+    Month,
+    /// The current week, starting on Monday (UTC)
+    Week,
+}
+
+impl FromStr for Period {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Period, ()> {
+        match s.to_lowercase().as_str() {
+            "all" | "alltime" | "all-time" => Ok(Period::AllTime),
+            "month" | "monthly" => Ok(Period::Month),
+            "week" | "weekly" => Ok(Period::Week),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Period {
+    // The earliest timestamp that falls inside this period
+    fn start(&self) -> i64 {
+        let today = Utc::now().date_naive();
+        match *self {
+            Period::AllTime => i64::MIN,
+            Period::Month => midnight(today - Duration::days(today.day0() as i64)),
+            Period::Week => {
+                let days_since_monday = today.weekday().num_days_from_monday() as i64;
+                midnight(today - Duration::days(days_since_monday))
+            }
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match *self {
+            Period::AllTime => "All-time",
+            Period::Month => "Monthly",
+            Period::Week => "Weekly",
+        }
+    }
+}
+
+// The timestamp of midnight UTC at the start of a day
+fn midnight(day: NaiveDate) -> i64 {
+    Utc.from_utc_datetime(&day.and_time(NaiveTime::MIN)).timestamp()
+}
+
+// A single player's result from a single game
+#[derive(Serialize, Deserialize)]
+struct GameResult {
+    guild: u64,
+    user: u64,
+    name: String,
+    points: i32,
+    played_at: i64,
+}
+
+/// Every game result ever recorded, kept in sync with a JSON file on disk
+pub struct Leaderboard {
+    path: PathBuf,
+    results: Vec<GameResult>,
+}
+
+impl Leaderboard {
+    /// Loads the leaderboard stored at `path`
+    ///
+    /// A missing file gives an empty leaderboard, which will be created on the first save.
+    pub fn load<P: Into<PathBuf>>(path: P) -> io::Result<Leaderboard> {
+        let path = path.into();
+        let results = store::load_json(&path)?;
+
+        Ok(Leaderboard { path, results })
+    }
+
+    /// Records the final scores of a game played in the given guild and saves them to disk
+    pub fn record_game(&mut self, guild: GuildId, scores: &[(UserId, String, i32)]) -> io::Result<()> {
+        let played_at = Utc::now().timestamp();
+        for &(user, ref name, points) in scores {
+            self.results.push(GameResult {
+                guild: guild.0,
+                user: user.0,
+                name: name.clone(),
+                points,
+                played_at,
+            });
+        }
+
+        self.save()
+    }
+
+    /// Outputs the top players of a guild over the given period as a String
+    pub fn output_leaderboard(&self, guild: GuildId, period: Period) -> String {
+        let since = period.start();
+
+        // Total up the points and games of each player in the period. The most recently
+        // recorded name is used for each player.
+        let mut totals: HashMap<u64, (String, i32, u32)> = HashMap::new();
+        for result in &self.results {
+            if result.guild != guild.0 || result.played_at < since {
+                continue;
+            }
+            let total = totals.entry(result.user).or_insert((String::new(), 0, 0));
+            total.0 = result.name.clone();
+            total.1 += result.points;
+            total.2 += 1;
+        }
+
+        let mut totals: Vec<(String, i32, u32)> = totals.into_iter().map(|(_, total)| total).collect();
+        totals.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        let mut output = format!("{} Leaderboard:\n", period.title());
+        if totals.is_empty() {
+            output.push_str("No games played yet\n");
+        }
+        for (i, &(ref name, points, games)) in totals.iter().take(10).enumerate() {
+            let s = format!("{}. {} - {} points ({} games)\n", i + 1, name, points, games);
+            output.push_str(&s);
+        }
+
+        output
+    }
+
+    // Writes the leaderboard to disk
+    fn save(&self) -> io::Result<()> {
+        store::save_json(&self.path, &self.results)
+    }
+}
@@ -0,0 +1,42 @@
+extern crate env_logger;
+
+use log::LevelFilter;
+use std::env;
+use std::time::Duration;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Whether the answer to every question is logged
+static LOG_ANSWERS: AtomicBool = AtomicBool::new(false);
+
+/// Sets up logging to stderr
+///
+/// Everything at info level and above is logged, unless RUST_LOG says otherwise (eg.
+/// `RUST_LOG=trivia_bot=debug`). Answers are only logged when TRIVIA_LOG_ANSWERS is set to 1
+/// or true, and then at debug level, so they never end up in logs by accident.
+pub fn init() {
+    let mut builder = env_logger::Builder::new();
+    builder.filter(None, LevelFilter::Info);
+    if let Ok(filters) = env::var("RUST_LOG") {
+        builder.parse(&filters);
+    }
+    builder.init();
+
+    let log_answers = match env::var("TRIVIA_LOG_ANSWERS") {
+        Ok(value) => value == "1" || value.eq_ignore_ascii_case("true"),
+        Err(_) => false,
+    };
+    LOG_ANSWERS.store(log_answers, Ordering::Relaxed);
+    if log_answers {
+        warn!("TRIVIA_LOG_ANSWERS is on, so the answer to every question will be logged at debug level");
+    }
+}
+
+/// Converts a duration to whole milliseconds
+pub fn millis(duration: Duration) -> u64 {
+    duration.as_secs() * 1000 + u64::from(duration.subsec_millis())
+}
+
+/// Checks if the answers to questions may be logged
+pub fn log_answers() -> bool {
+    LOG_ANSWERS.load(Ordering::Relaxed)
+}
@@ -8,6 +8,9 @@ extern crate serde_derive;
 extern crate htmlescape;
 extern crate url;
 extern crate rand;
+extern crate chrono;
+#[macro_use]
+extern crate log;
 
 //Imports
 use serenity::client::{Client, Context};
@@ -15,14 +18,35 @@ use serenity::framework::standard::StandardFramework;
 use serenity::model::channel::Message;
 use serenity::prelude::EventHandler;
 use std::env;
+use std::sync::Arc;
 
 //Modules
+pub mod access;
+pub mod answer;
+pub mod cache;
 pub mod commands;
+pub mod config;
 pub mod db;
+pub mod error;
+pub mod fuzzy;
+pub mod help;
+pub mod hint;
+pub mod leaderboard;
+pub mod logging;
 pub mod optionset;
+pub mod pack;
+pub mod present;
 pub mod question;
 pub mod questionset;
 pub mod scores;
+pub mod scoring;
+pub mod session;
+pub mod skip;
+pub mod source;
+pub mod startargs;
+pub mod store;
+pub mod teams;
+pub mod timer;
 pub mod trivia;
 
 struct Handler;
@@ -38,34 +62,72 @@ impl EventHandler for Handler {
 }
 
 fn main() {
+    logging::init();
+
     // Login with a bot token from the environment
     let discord_token = &env::var("DISCORD_TOKEN").expect("token");
-    let trivia_manager = trivia::TriviaManager::new();
+
+    // Load the all-time leaderboard, which is kept next to the bot unless told otherwise
+    let leaderboard_path = env::var("TRIVIA_LEADERBOARD").unwrap_or_else(|_| "leaderboard.json".to_string());
+    let leaderboard = leaderboard::Leaderboard::load(leaderboard_path).expect("Error loading leaderboard");
+
+    // Questions come from the local packs in TRIVIA_PACKS if it is set, and from the Open
+    // Trivia Database otherwise. Packs are already in memory, so only the database's questions
+    // are kept ready in pools.
+    let questions = match env::var("TRIVIA_PACKS") {
+        Ok(directory) => {
+            let packs = pack::FilePacks::load(directory).expect("Error loading question packs");
+            cache::QuestionCache::new(Box::new(packs), 0)
+        }
+        Err(_) => {
+            let token_path = env::var("TRIVIA_TOKENS").unwrap_or_else(|_| "tokens.json".to_string());
+            let opentdb = db::OpenTdb::new(token_path).expect("Error loading session tokens");
+            cache::QuestionCache::new(Box::new(opentdb), cache::POOL_SIZE)
+        }
+    };
+    let questions = Arc::new(questions);
+
+    // Load who can control games in each guild
+    let access_path = env::var("TRIVIA_PERMISSIONS").unwrap_or_else(|_| "permissions.json".to_string());
+    let access = access::AccessControl::load(access_path).expect("Error loading permission rules");
+
+    // Load the default settings from TRIVIA_CONFIG and the environment, and the settings each
+    // guild has changed
+    let config_path = env::var("TRIVIA_CONFIG").unwrap_or_else(|_| "trivia.toml".to_string());
+    let guild_config_path = env::var("TRIVIA_GUILD_CONFIG").unwrap_or_else(|_| "guilds.json".to_string());
+    let config = config::Config::load(config_path, guild_config_path).expect("Error loading configuration");
+    let default_prefix = config.settings(None).prefix;
+
+    // Start pulling the category list before anyone asks for it
+    cache::QuestionCache::warm(&questions);
 
     // Setup the bot client.
     let mut client = Client::new(discord_token, Handler).expect("Error creating client");
 
     // Store the trivia manager in our context's data map
     {
+        let trivia_manager = trivia::TriviaManager::new(client.data.clone(), leaderboard, questions, access, config);
         let mut data = client.data.lock();
         data.insert::<trivia::TriviaManager>(trivia_manager);
     }
 
     // Construct a client handler, which routes trivia commands to logic.
     // This does not handle the answer input, only commands given with the prefix character.
-    client.with_framework(
+    // Guilds which have chosen their own prefix use it instead of the default.
+    // The commands are registered from help::COMMANDS, which .thelp lists.
+    client.with_framework(help::register(
         StandardFramework::new()
-        .configure(|c| c.prefix(".")) // set the bot's prefix to "."
-        .command("tstart", |c| c
-            .cmd(commands::trivia_start))
-        .command("tstop", |c| c
-            .cmd(commands::trivia_stop))
-        .command("tskip", |c| c
-            .cmd(commands::trivia_skip)),
-    );
+        .configure(|c| c
+            .prefix(&default_prefix)
+            .dynamic_prefix(|ctx, msg| {
+                let guild = msg.guild_id?;
+                let data = ctx.data.lock();
+                data.get::<trivia::TriviaManager>()?.guild_prefix(guild)
+            }))
+    ));
 
     // Start listening for events by starting a single shard
     if let Err(why) = client.start() {
-        println!("An error occurred while running the client: {:?}", why);
+        error!("client stopped error={:?}", why);
     }
 }
@@ -1,8 +1,42 @@
-use serenity::model::id::ChannelId;
+use serenity::model::id::{ChannelId, GuildId, UserId};
+use std::time::Duration;
 
+use answer::AnswerMode;
+use hint::HintMode;
+use question::QuestionType;
+use scoring::Scoring;
+use skip::SkipThreshold;
+use teams::TeamMode;
+
+#[derive(Clone)]
 pub struct OptionSet {
     pub number_of_questions: u32,
     pub difficulty: String,
     pub category: String,
+    /// The type of question to ask. None mixes every type.
+    pub question_type: Option<QuestionType>,
+    /// Whether players pick from the options or type the answer
+    pub answer_mode: AnswerMode,
+    /// How correct answers are scored
+    pub scoring: Scoring,
+    /// The points taken away for picking a wrong option. 0 turns negative marking off.
+    pub wrong_penalty: u32,
+    /// How many votes it takes to skip a question
+    pub skip_threshold: SkipThreshold,
+    /// Whether players score for themselves or for a team
+    pub team_mode: TeamMode,
     pub channel: ChannelId,
+    /// The guild the game is played in. None for direct messages.
+    pub guild: Option<GuildId>,
+    /// The player who started the game
+    pub started_by: UserId,
+    /// How long each question stays open before the answer is revealed. None waits forever.
+    pub time_limit: Option<Duration>,
+    /// Whether the game gives hints, which decides if unused hints are worth points
+    pub hint_mode: HintMode,
+    /// How often a hint is given automatically while a question is open. None only gives hints
+    /// when asked for.
+    pub hint_interval: Option<Duration>,
+    /// The prefix commands start with where the game is played, for telling players what to type
+    pub prefix: String,
 }
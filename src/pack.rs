@@ -0,0 +1,218 @@
+extern crate csv;
+extern crate serde_json;
+extern crate toml;
+
+use rand::{thread_rng, Rng};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::Path;
+
+use error::Result as TriviaResult;
+use optionset::OptionSet;
+use question::{Question, QuestionType};
+use questionset::QuestionSet;
+use source::{Category, QuestionSource};
+
+// A single question as it is written in a pack file
+#[derive(Clone, Deserialize)]
+struct PackEntry {
+    question: String,
+    correct_answer: String,
+    incorrect_answers: Vec<String>,
+    category: Option<String>,
+    difficulty: Option<String>,
+}
+
+// The layout of a TOML pack, which lists its questions as [[questions]] tables.
+// JSON packs may use the same layout, or be a bare list of questions.
+#[derive(Deserialize)]
+struct PackFile {
+    questions: Vec<PackEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonPack {
+    List(Vec<PackEntry>),
+    File(PackFile),
+}
+
+/// Pulls questions from question packs stored as files in a local directory
+///
+/// Every `.json`, `.toml` and `.csv` file in the directory is a pack. Questions without a
+/// category are filed under the name of their pack, and questions without a difficulty can be
+/// asked at any difficulty.
+///
+/// CSV packs need a header row with `question` and `correct_answer` columns, any number of
+/// columns starting with `incorrect_answer`, and optionally `category` and `difficulty` columns.
+pub struct FilePacks {
+    entries: Vec<PackEntry>,
+}
+
+impl FilePacks {
+    /// Loads every pack in the given directory
+    pub fn load<P: AsRef<Path>>(directory: P) -> io::Result<FilePacks> {
+        let mut entries = Vec::new();
+
+        for file in fs::read_dir(directory)? {
+            let path = file?.path();
+            let pack_name = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(stem) => stem.to_string(),
+                None => continue,
+            };
+
+            let pack = match path.extension().and_then(|ext| ext.to_str()) {
+                Some("json") => load_json(&path),
+                Some("toml") => load_toml(&path),
+                Some("csv") => load_csv(&path),
+                _ => continue,
+            };
+
+            let pack = match pack {
+                Ok(pack) => pack,
+                Err(why) => {
+                    warn!("skipping question pack path={} error={}", path.display(), why);
+                    continue;
+                }
+            };
+
+            for mut entry in pack {
+                // Every option needs its own letter
+                if entry.incorrect_answers.is_empty() || entry.incorrect_answers.len() > 25 {
+                    warn!("skipping question without 1 to 25 incorrect answers path={} question={:?}",
+                          path.display(), entry.question);
+                    continue;
+                }
+                if entry.category.is_none() {
+                    entry.category = Some(pack_name.clone());
+                }
+                entries.push(entry);
+            }
+        }
+
+        Ok(FilePacks { entries })
+    }
+}
+
+impl QuestionSource for FilePacks {
+    fn get_question_set(&self, options: &OptionSet) -> TriviaResult<QuestionSet> {
+        // Pick out the questions which match the options
+        let mut matching: Vec<&PackEntry> = self.entries
+            .iter()
+            .filter(|entry| matches(&entry.category, &options.category))
+            .filter(|entry| matches(&entry.difficulty, &options.difficulty))
+            .filter(|entry| match options.question_type {
+                Some(question_type) => {
+                    QuestionType::of(&entry.correct_answer, &entry.incorrect_answers) == question_type
+                }
+                None => true,
+            })
+            .collect();
+
+        // Ask a random selection of them
+        thread_rng().shuffle(&mut matching);
+        // Packs are plain text, so nothing in them is HTML-decoded
+        let questions = matching
+            .into_iter()
+            .take(options.number_of_questions as usize)
+            .map(|entry| Question::from_text(entry.question.clone(),
+                                             entry.correct_answer.clone(),
+                                             entry.incorrect_answers.clone(),
+                                             entry.category.clone().unwrap_or_default(),
+                                             entry.difficulty.clone().unwrap_or_else(|| "any".to_string()),
+                                             false))
+            .collect();
+
+        Ok(QuestionSet::new(questions))
+    }
+
+    fn categories(&self) -> TriviaResult<Vec<Category>> {
+        // Count the questions in each category, keeping the categories in alphabetical order
+        let mut counts: BTreeMap<&str, u32> = BTreeMap::new();
+        for entry in &self.entries {
+            if let Some(ref category) = entry.category {
+                *counts.entry(category.as_str()).or_insert(0) += 1;
+            }
+        }
+
+        Ok(counts
+            .into_iter()
+            .map(|(name, count)| Category {
+                id: name.to_string(),
+                name: name.to_string(),
+                count: Some(count),
+            })
+            .collect())
+    }
+}
+
+// Checks a question's value against the value asked for. An empty request matches anything, as
+// does a question which doesn't set a value.
+fn matches(value: &Option<String>, wanted: &str) -> bool {
+    match *value {
+        Some(ref value) => wanted.is_empty() || value.eq_ignore_ascii_case(wanted),
+        None => true,
+    }
+}
+
+fn read_file(path: &Path) -> io::Result<String> {
+    let mut contents = String::new();
+    File::open(path)?.read_to_string(&mut contents)?;
+    Ok(contents)
+}
+
+fn load_json(path: &Path) -> Result<Vec<PackEntry>, String> {
+    let contents = read_file(path).map_err(|e| e.to_string())?;
+    match serde_json::from_str(&contents).map_err(|e| e.to_string())? {
+        JsonPack::List(entries) => Ok(entries),
+        JsonPack::File(pack) => Ok(pack.questions),
+    }
+}
+
+fn load_toml(path: &Path) -> Result<Vec<PackEntry>, String> {
+    let contents = read_file(path).map_err(|e| e.to_string())?;
+    let pack: PackFile = toml::from_str(&contents).map_err(|e| e.to_string())?;
+    Ok(pack.questions)
+}
+
+fn load_csv(path: &Path) -> Result<Vec<PackEntry>, String> {
+    let mut reader = csv::Reader::from_path(path).map_err(|e| e.to_string())?;
+    let headers = reader.headers().map_err(|e| e.to_string())?.clone();
+
+    let column = |name: &str| headers.iter().position(|header| header.trim() == name);
+    let question_column = column("question").ok_or("missing a question column")?;
+    let answer_column = column("correct_answer").ok_or("missing a correct_answer column")?;
+    let category_column = column("category");
+    let difficulty_column = column("difficulty");
+    let incorrect_columns: Vec<usize> = headers
+        .iter()
+        .enumerate()
+        .filter(|&(_, header)| header.trim().starts_with("incorrect_answer"))
+        .map(|(i, _)| i)
+        .collect();
+
+    let mut entries = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| e.to_string())?;
+        let field = |i: usize| record.get(i).unwrap_or("").trim().to_string();
+        // Empty optional cells are treated the same as a missing column
+        let optional = |i: Option<usize>| i.map(&field).and_then(|value| {
+            if value.is_empty() { None } else { Some(value) }
+        });
+
+        entries.push(PackEntry {
+            question: field(question_column),
+            correct_answer: field(answer_column),
+            incorrect_answers: incorrect_columns
+                .iter()
+                .map(|&i| field(i))
+                .filter(|answer| !answer.is_empty())
+                .collect(),
+            category: optional(category_column),
+            difficulty: optional(difficulty_column),
+        });
+    }
+
+    Ok(entries)
+}
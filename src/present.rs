@@ -0,0 +1,180 @@
+use serenity::builder::CreateEmbed;
+use serenity::model::channel::Channel;
+use serenity::model::id::ChannelId;
+use serenity::model::permissions::Permissions;
+use serenity::utils::Colour;
+use serenity::CACHE;
+use std::time::Duration;
+
+use error::Result;
+use question::Question;
+use scores::Scores;
+use scoring::Breakdown;
+
+/// A question as it is posted to players
+pub struct QuestionPost<'a> {
+    pub question: &'a Question,
+    /// The question's number, starting at 1
+    pub number: usize,
+    /// The number of questions in the game
+    pub total: usize,
+    pub time_limit: Option<Duration>,
+    /// Whether the options are listed. Typed answer games hide them.
+    pub show_options: bool,
+}
+
+/// The answer to a question, posted once it has been answered or its time has run out
+pub struct RevealPost<'a> {
+    pub question: &'a Question,
+    /// Who answered it, eg. "Alice (Red)". None if nobody did.
+    pub answered_by: Option<String>,
+    /// The points the answer was worth
+    pub points: Option<&'a Breakdown>,
+    /// Whether the answer is shown with its option label
+    pub show_label: bool,
+}
+
+/// The scores of a game which is still running
+pub struct StandingsPost<'a> {
+    pub scores: &'a Scores,
+    /// The number of the question being asked, starting at 1
+    pub number: usize,
+    /// The number of questions in the game
+    pub total: usize,
+}
+
+/// Everything a game posts which has a layout of its own
+///
+/// Each post is sent as an embed, or as plain text in channels where the bot can't embed links.
+pub enum Post<'a> {
+    Question(QuestionPost<'a>),
+    Reveal(RevealPost<'a>),
+    /// The standings partway through a game
+    Standings(StandingsPost<'a>),
+    Results(&'a Scores),
+}
+
+/// Sends a post to a channel
+pub fn send(channel: ChannelId, post: &Post) -> Result<()> {
+    if can_embed(channel) {
+        channel.send_message(|m| m.embed(|e| embed(post, e)))?;
+    } else {
+        channel.say(text(post))?;
+    }
+
+    Ok(())
+}
+
+/// Checks if the bot is allowed to post embeds in a channel
+///
+/// Channels outside of a guild always allow them. If the channel or its guild isn't cached,
+/// embeds are assumed to be allowed.
+pub fn can_embed(channel: ChannelId) -> bool {
+    let guild_channel = match channel.to_channel_cached() {
+        Some(Channel::Guild(guild_channel)) => guild_channel,
+        _ => return true,
+    };
+    let bot = CACHE.read().user.id;
+    let permissions = guild_channel.read().permissions_for(bot);
+
+    match permissions {
+        Ok(permissions) => permissions.contains(Permissions::EMBED_LINKS),
+        Err(_) => true,
+    }
+}
+
+// Fills in an embed for a post
+fn embed(post: &Post, e: CreateEmbed) -> CreateEmbed {
+    match *post {
+        Post::Question(ref p) => {
+            let q = p.question;
+            let mut e = e
+                .title(format!("Question {}/{}", p.number, p.total))
+                .colour(difficulty_colour(&q.difficulty))
+                .description(&q.prompt);
+            if p.show_options {
+                e = e.field("Options", &q.answer_prompt, false);
+            }
+            // Discord rejects fields with nothing in them
+            if !q.category.is_empty() {
+                e = e.field("Category", &q.category, true);
+            }
+            if !q.difficulty.is_empty() {
+                e = e.field("Difficulty", capitalize(&q.difficulty), true);
+            }
+            e.footer(|f| f.text(match p.time_limit {
+                Some(limit) => format!("{} seconds to answer", limit.as_secs()),
+                None => String::from("No time limit"),
+            }))
+        }
+        Post::Reveal(ref p) => {
+            let e = match p.answered_by {
+                Some(ref name) => e.title(format!("{} got the correct answer!", name)).colour(Colour::DARK_GREEN),
+                None => e.title("Time's up!").colour(Colour::RED),
+            };
+            let e = e.description(format!("The correct answer was **{}**", answer(p)));
+            match p.points {
+                Some(points) => e.field("Points", format!("+{} ({})", points.total(), points), true),
+                None => e,
+            }
+        }
+        Post::Standings(ref p) => {
+            p.scores.scores_embed(e, &format!("Standings at Question {}/{}", p.number, p.total))
+        }
+        Post::Results(scores) => scores.scores_embed(e, "Final Results"),
+    }
+}
+
+// Writes a post out as plain text
+fn text(post: &Post) -> String {
+    match *post {
+        Post::Question(ref p) => {
+            if p.show_options {
+                format!("```Question {}/{}: {}\n{}```", p.number, p.total, p.question.prompt, p.question.answer_prompt)
+            } else {
+                format!("```Question {}/{}: {}```", p.number, p.total, p.question.prompt)
+            }
+        }
+        Post::Reveal(ref p) => match (&p.answered_by, p.points) {
+            (&Some(ref name), Some(points)) => {
+                format!("{} got the correct answer! The correct answer was **{}**\n**+{} points** ({})",
+                        name, answer(p), points.total(), points)
+            }
+            (&Some(ref name), None) => format!("{} got the correct answer! The correct answer was **{}**", name, answer(p)),
+            (&None, _) => format!("Time's up! The correct answer was **{}**", answer(p)),
+        },
+        Post::Standings(ref p) => {
+            format!("Standings at Question {}/{}\n{}", p.number, p.total, p.scores.output_scores())
+        }
+        Post::Results(scores) => scores.output_scores(),
+    }
+}
+
+// The answer to a revealed question, with its label if it has one
+fn answer(post: &RevealPost) -> String {
+    let q = post.question;
+    if post.show_label {
+        format!("{}. {}", q.answer_letter, q.answer)
+    } else {
+        q.answer.clone()
+    }
+}
+
+// Easy questions are green, medium ones gold and hard ones red
+fn difficulty_colour(difficulty: &str) -> Colour {
+    match difficulty.to_lowercase().as_str() {
+        "easy" => Colour::DARK_GREEN,
+        "medium" => Colour::GOLD,
+        "hard" => Colour::RED,
+        _ => Colour::BLURPLE,
+    }
+}
+
+// Uppercases the first letter of a word, eg. "medium" to "Medium"
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
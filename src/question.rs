@@ -1,13 +1,62 @@
 use std::fmt::{Display, Formatter, Result};
 use rand::{thread_rng, Rng};
 use htmlescape::decode_html;
+use std::str::FromStr;
 
+use error::{Error, Result as TriviaResult};
+use logging;
+
+/// The kinds of question that can be asked
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum QuestionType {
+    /// One correct answer picked out of several lettered options
+    Multiple,
+    /// A statement which is either True or False
+    Boolean,
+}
+
+impl QuestionType {
+    /// The name the Open Trivia Database uses for this type of question
+    pub fn name(&self) -> &'static str {
+        match *self {
+            QuestionType::Multiple => "multiple",
+            QuestionType::Boolean => "boolean",
+        }
+    }
+
+    /// Works out the type of a question from its answers
+    ///
+    /// A question whose only answers are "True" and "False" is a Boolean question
+    pub fn of(answer: &str, incorrect_answers: &[String]) -> QuestionType {
+        let is_bool = |s: &str| s.eq_ignore_ascii_case("true") || s.eq_ignore_ascii_case("false");
+        if incorrect_answers.len() == 1 && is_bool(answer) && is_bool(&incorrect_answers[0]) {
+            QuestionType::Boolean
+        } else {
+            QuestionType::Multiple
+        }
+    }
+}
+
+impl FromStr for QuestionType {
+    type Err = ();
+
+    fn from_str(s: &str) -> ::std::result::Result<QuestionType, ()> {
+        match s.to_lowercase().as_str() {
+            "multiple" => Ok(QuestionType::Multiple),
+            "boolean" | "truefalse" => Ok(QuestionType::Boolean),
+            _ => Err(()),
+        }
+    }
+}
 
 pub struct Question {
     pub prompt: String,
     pub answer: String,
+    pub answer_index: usize,
     pub answer_letter: String,
     pub answer_prompt: String,
+    pub options: Vec<String>,
+    pub question_type: QuestionType,
     pub category: String,
     pub difficulty: String,
     pub answered: bool,
@@ -15,38 +64,62 @@ pub struct Question {
 
 impl Question {
 
+    /// Creates a question from HTML-encoded text, as the Open Trivia Database sends it
     pub fn new(prompt: String, 
                answer: String,
                incorrect_answers: Vec<String>,
                category: String,
                difficulty: String,
-               answered: bool) -> Question {
+               answered: bool) -> TriviaResult<Question> {
         
+        Ok(Question::from_text(decode(&prompt)?,
+                               decode(&answer)?,
+                               decode_html_vector(incorrect_answers)?,
+                               category,
+                               difficulty,
+                               answered))
+    }
+
+    /// Creates a question from plain text, which is shown exactly as it's written
+    pub fn from_text(prompt: String,
+                     answer: String,
+                     incorrect_answers: Vec<String>,
+                     category: String,
+                     difficulty: String,
+                     answered: bool) -> Question {
+        let question_type = QuestionType::of(&answer, &incorrect_answers);
         let mut question = Question {
-            prompt: decode_html(&prompt).expect("Error decoding a question prompt"),
-            answer: decode_html(&answer).expect("Error decoding a question answer"),
+            prompt,
+            answer,
+            answer_index: 0,
             answer_letter: String::from("A"),
             answer_prompt: String::from(""), 
+            options: Vec::new(),
+            question_type,
             category,
             difficulty,
             answered,
         };
         // Randomize the answer set and set our answer letter to the corrisponding answer
-        question.set_answer_prompt(decode_html_vector(incorrect_answers));
+        question.set_answer_prompt(incorrect_answers);
 
         question
     }
 
-    // Sets answer_letter and answer_prompt
+    // Sets answer_index, answer_letter, answer_prompt and options
     // This function should ONLY be used after the question information has been generated
     fn set_answer_prompt(&mut self, incorrect_answers: Vec<String>) {
         //Put all of our question's potential answers in a vector
         let mut answers = incorrect_answers;
         answers.push(self.answer.clone());
         
-        //Shuffle the vector
-        let mut rng = thread_rng();
-        rng.shuffle(&mut answers);
+        //Shuffle the vector. True/False answers are always listed as True then False.
+        if self.question_type == QuestionType::Boolean {
+            answers.sort_by_key(|answer| !answer.eq_ignore_ascii_case("true"));
+        } else {
+            let mut rng = thread_rng();
+            rng.shuffle(&mut answers);
+        }
 
         //Search our shuffled vector for our correct answer.
         let mut index = 0;
@@ -56,40 +129,84 @@ impl Question {
             }
         }
                 
-        //Store the correct answer's letter for answer checking based on the index
-        self.answer_letter = match index {
-            0 => String::from("A"),
-            1 => String::from("B"),
-            2 => String::from("C"),
-            3 => String::from("D"),
-            _ => String::from("A"),
-        };
-        
-        //Return a String of a formatted list of potential answers
-        self.answer_prompt = format!("\nA. {}\nB. {}\nC. {}\nD. {}\n",
-                answers[0],
-                answers[1],
-                answers[2],
-                answers[3])
+        //Store the shuffled answers, the correct answer's place among them, and a formatted
+        //list of them
+        self.options = answers;
+        self.answer_index = index;
+        self.answer_letter = self.label(index);
+        self.answer_prompt = self.options_prompt(&[]);
+    }
+
+    /// The label players use to pick the option at the given index
+    ///
+    /// Multiple choice options are lettered from A, and True/False options are T and F
+    pub fn label(&self, index: usize) -> String {
+        match self.question_type {
+            QuestionType::Multiple => ((b'A' + index as u8) as char).to_string(),
+            QuestionType::Boolean => self.options[index].chars().take(1).collect::<String>().to_uppercase(),
+        }
+    }
+
+    /// Works out which option a message picks, returning its index
+    ///
+    /// Options are picked by their label, and True/False options can also be picked by typing
+    /// "true" or "false". Returns None if the message doesn't pick an option.
+    pub fn choice(&self, message: &str) -> Option<usize> {
+        let message = message.trim().to_lowercase();
+        (0..self.options.len()).find(|&i| {
+            message == self.label(i).to_lowercase() ||
+                (self.question_type == QuestionType::Boolean && message == self.options[i].to_lowercase())
+        })
+    }
+
+    /// Returns the indexes into `options` of every wrong answer
+    pub fn wrong_options(&self) -> Vec<usize> {
+        self.options.iter()
+            .enumerate()
+            .filter(|&(_, option)| option != &self.answer)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Formats the list of potential answers, striking out the options at the given indexes
+    pub fn options_prompt(&self, eliminated: &[usize]) -> String {
+        let mut prompt = String::from("\n");
+        for (i, option) in self.options.iter().enumerate() {
+            if eliminated.contains(&i) {
+                prompt.push_str(&format!("~~{}. {}~~\n", self.label(i), option));
+            } else {
+                prompt.push_str(&format!("{}. {}\n", self.label(i), option));
+            }
+        }
+
+        prompt
     }
 
 }
 
 // HTML Decode a vector of strings in-place
-fn decode_html_vector(mut answers: Vec<String>) -> Vec<String> {
+fn decode_html_vector(mut answers: Vec<String>) -> TriviaResult<Vec<String>> {
     for answer in &mut answers {
-        *answer = decode_html(answer).expect("Error decoding an incorrect answer");
+        *answer = decode(answer)?;
     }
 
-    answers
+    Ok(answers)
 }
 
+// HTML Decode a single string, keeping the text that failed to decode for the error
+fn decode(text: &str) -> TriviaResult<String> {
+    decode_html(text).map_err(|_| Error::Decode(text.to_string()))
+}
+
+/// The answer is left out unless answers may be logged
 impl Display for Question {
     fn fmt(&self, f: &mut Formatter) -> Result {
-        writeln!(
-            f,
-            "\tCat: {},\n\tDifficulty: {},\n\tQuestion: {},\n\tAnswer: {}\n",
-            self.category, self.difficulty, self.prompt, self.answer
-        )
+        writeln!(f, "\tCat: {},\n\tDifficulty: {},\n\tQuestion: {},",
+                 self.category, self.difficulty, self.prompt)?;
+        if logging::log_answers() {
+            writeln!(f, "\tAnswer: {}", self.answer)?;
+        }
+
+        Ok(())
     }
 }
@@ -3,25 +3,66 @@ use std::fmt::{Display, Formatter, Result};
 
 pub struct QuestionSet {
     questions: Vec<Question>,
-    _number_of_questions: u32,
     current_question_number: usize,
+    adjustments: Vec<String>,
 }
 
 impl QuestionSet {
     /// Generates a new QuestionSet item 
-    pub fn new(questions: Vec<Question>, number_of_questions: u32) -> QuestionSet {
+    /// The set's size is the number of questions it's given
+    pub fn new(questions: Vec<Question>) -> QuestionSet {
         QuestionSet {
             questions,
-            _number_of_questions: number_of_questions,
             current_question_number: 0,
+            adjustments: Vec::new(),
         }
     }
 
+    /// Notes a way the source had to change the game's options to find these questions
+    pub fn add_adjustment(&mut self, adjustment: String) {
+        self.adjustments.push(adjustment);
+    }
+
+    /// Gets every change the source made to the game's options, so players can be told
+    pub fn adjustments(&self) -> &[String] {
+        &self.adjustments
+    }
+
     /// Gets the current question as an Option
     pub fn get_current_question(&self) -> Option<&Question> {
         self.questions.get(self.current_question_number)
     }
 
+    /// Gets the current question as a mutable Option
+    pub fn get_current_question_mut(&mut self) -> Option<&mut Question> {
+        self.questions.get_mut(self.current_question_number)
+    }
+
+    /// Gets the index of the current question, starting at 0
+    pub fn position(&self) -> usize {
+        self.current_question_number
+    }
+
+    /// Gets the number of the current question as players count it, starting at 1
+    pub fn current_question_number(&self) -> usize {
+        self.current_question_number + 1
+    }
+
+    /// Gets the number of questions in the set
+    pub fn len(&self) -> usize {
+        self.questions.len()
+    }
+
+    /// Checks if the set has no questions at all
+    pub fn is_empty(&self) -> bool {
+        self.questions.is_empty()
+    }
+
+    /// Takes the questions out of the set
+    pub fn into_questions(self) -> Vec<Question> {
+        self.questions
+    }
+
     /// Changes the current question to the next question in the QuestionSet
     pub fn next_question(&mut self) {
         self.current_question_number += 1;
@@ -29,13 +70,11 @@ impl QuestionSet {
 }
 
 impl Display for QuestionSet {
-    fn fmt(&self, _f: &mut Formatter) -> Result {
-        println!("\n[");
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        writeln!(f, "[")?;
         for question in &self.questions {
-            println!("{}, ", question);
+            writeln!(f, "{},", question)?;
         }
-        println!("\n]");
-
-        Ok(())
+        write!(f, "]")
     }
 }
@@ -1,9 +1,66 @@
+use serenity::builder::CreateEmbed;
 use serenity::model::id::UserId;
 use serenity::model::user::User;
+use serenity::utils::Colour;
+use std::cmp::Reverse;
 use std::collections::HashMap;
+use std::time::Duration;
+
+// The most players listed in the results
+const MAX_LISTED: usize = 20;
+// The medals given to the top three places
+const MEDALS: [&str; 3] = ["🥇", "🥈", "🥉"];
+
+/// A team's name, total score and its members' (user name, score), best first
+pub type TeamResult = (String, i32, Vec<(String, i32)>);
+
+/// What happened to a question by the time the game moved on from it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Outcome {
+    /// Someone answered it correctly
+    Answered,
+    /// Players voted to skip it
+    Skipped,
+    /// Its time ran out before anyone answered it
+    Unanswered,
+}
+
+/// A player's place in the results of a game
+pub struct Standing {
+    /// The player's rank. Players with the same score share a rank.
+    pub rank: usize,
+    pub name: String,
+    pub score: i32,
+    /// The number of questions the player answered correctly
+    pub correct: u32,
+    /// The number of questions the player took a shot at
+    pub attempts: u32,
+}
+
+impl Standing {
+    /// The percentage of the player's attempts which were correct
+    pub fn accuracy(&self) -> u32 {
+        // No attempts counts as 0%
+        (self.correct * 100).checked_div(self.attempts).unwrap_or(0)
+    }
+}
 
 pub struct Scores {
-    score_list: HashMap<UserId, (String, u32)>,
+    score_list: HashMap<UserId, (String, i32)>,
+    team_list: HashMap<UserId, String>,
+    // Every player's (correct answers, attempts)
+    stats: HashMap<UserId, (u32, u32)>,
+    // The quickest correct answer of the game, as (user name, time taken)
+    fastest: Option<(String, Duration)>,
+    outcomes: Vec<Outcome>,
+    // Whether the standings show each player's accuracy
+    show_accuracy: bool,
+}
+
+impl Default for Scores {
+    fn default() -> Scores {
+        Scores::new()
+    }
 }
 
 impl Scores {
@@ -11,34 +68,327 @@ impl Scores {
     pub fn new() -> Scores {
         Scores {
             score_list: HashMap::new(),
+            team_list: HashMap::new(),
+            stats: HashMap::new(),
+            fastest: None,
+            outcomes: Vec::new(),
+            show_accuracy: true,
         }
     }
 
+    /// Leaves accuracy out of the standings, for games where wrong guesses can't be told apart
+    /// from the rest of the chat
+    pub fn hide_accuracy(&mut self) {
+        self.show_accuracy = false;
+    }
+
     /// Increase the score of a User by a given amount
     /// User is of type serenity::model::user::User
-    pub fn increase_score(&mut self, user: User, points: u32) {
+    pub fn increase_score(&mut self, user: User, points: i32) {
         let old_score = self.get_score(&user);
         self.score_list
             .insert(user.id, (user.name, old_score + points));
     }
 
+    /// Decrease the score of a User by a given amount. Scores can go below zero.
+    /// User is of type serenity::model::user::User
+    pub fn decrease_score(&mut self, user: User, points: i32) {
+        self.increase_score(user, -points);
+    }
+
     /// Get the score of the user as a number
     /// User is of type serenity::model::user::User
-    pub fn get_score(&self, user: &User) -> u32 {
+    pub fn get_score(&self, user: &User) -> i32 {
         match self.score_list.get(&user.id) {
             Some(s) => s.1,
             None => 0,
         }
     }
 
+    /// Records a player's attempt at a question, and whether it was correct
+    /// The player is put on the score list even if they haven't scored
+    pub fn record_attempt(&mut self, user: &User, correct: bool) {
+        self.score_list.entry(user.id).or_insert_with(|| (user.name.clone(), 0));
+
+        let stats = self.stats.entry(user.id).or_insert((0, 0));
+        if correct {
+            stats.0 += 1;
+        }
+        stats.1 += 1;
+    }
+
+    /// Records how long a correct answer took, keeping it if it's the fastest of the game
+    pub fn record_time(&mut self, user: &User, elapsed: Duration) {
+        let faster = match self.fastest {
+            Some((_, fastest)) => elapsed < fastest,
+            None => true,
+        };
+        if faster {
+            self.fastest = Some((user.name.clone(), elapsed));
+        }
+    }
+
+    /// Records what happened to a question once the game moves on from it
+    pub fn record_question(&mut self, outcome: Outcome) {
+        self.outcomes.push(outcome);
+    }
+
+    /// The number of questions which ended with the given outcome
+    pub fn count_questions(&self, outcome: Outcome) -> usize {
+        self.outcomes.iter().filter(|&&o| o == outcome).count()
+    }
+
+    /// Records the team a user is scoring for
+    /// A user's points all count towards the last team set for them
+    pub fn set_team(&mut self, user: UserId, team: String) {
+        self.team_list.insert(user, team);
+    }
+
+    /// Get every team's total score and its members' scores, highest total first
+    pub fn team_results(&self) -> Vec<TeamResult> {
+        let mut teams = HashMap::new();
+        for (userid, team) in &self.team_list {
+            if let Some(score) = self.score_list.get(userid) {
+                let entry = teams.entry(team.as_str()).or_insert((0, Vec::new()));
+                entry.0 += score.1;
+                entry.1.push((score.0.clone(), score.1));
+            }
+        }
+
+        let mut teams: Vec<TeamResult> = teams
+            .into_iter()
+            .map(|(team, (total, mut members))| {
+                members.sort_by_key(|member| Reverse(member.1));
+                (team.to_string(), total, members)
+            })
+            .collect();
+        teams.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        teams
+    }
+
+    /// Get every user's final score as (user id, user name, score)
+    pub fn results(&self) -> Vec<(UserId, String, i32)> {
+        self.score_list
+            .iter()
+            .map(|(userid, score)| (*userid, score.0.clone(), score.1))
+            .collect()
+    }
+
+    /// Get every player's standing, highest score first
+    ///
+    /// Players with the same score share a rank, and the next rank skips the places they
+    /// took up (eg. 1, 1, 3).
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut standings: Vec<Standing> = self.score_list
+            .iter()
+            .map(|(userid, score)| {
+                let (correct, attempts) = self.stats.get(userid).cloned().unwrap_or((0, 0));
+                Standing {
+                    rank: 0,
+                    name: score.0.clone(),
+                    score: score.1,
+                    correct,
+                    attempts,
+                }
+            })
+            .collect();
+        standings.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.name.cmp(&b.name)));
+
+        // Players tied with the one above them share their rank
+        let mut above: Option<(i32, usize)> = None;
+        for (i, standing) in standings.iter_mut().enumerate() {
+            standing.rank = match above {
+                Some((score, rank)) if score == standing.score => rank,
+                _ => i + 1,
+            };
+            above = Some((standing.score, standing.rank));
+        }
+
+        standings
+    }
+
     /// Output all the scores as a String
+    /// When players scored for teams, the team ranking comes first
     pub fn output_scores(&self) -> String {
-        let mut output = String::from("Scores:\n");
-        for (userid, score) in &self.score_list {
-            let s = format!("{} - {} - {}\n", userid, score.0, score.1);
+        let mut output = String::new();
+
+        let teams = self.output_teams();
+        if !teams.is_empty() {
+            output.push_str("Team Scores:\n");
+            output.push_str(&teams);
+            output.push('\n');
+        }
+
+        output.push_str("Scores:\n");
+        output.push_str(&self.output_standings());
+        output.push_str(&self.output_stats());
+
+        output
+    }
+
+    /// Fills in an embed with the scores and statistics of the game so far
+    pub fn scores_embed(&self, embed: CreateEmbed, title: &str) -> CreateEmbed {
+        let mut embed = embed
+            .title(title)
+            .colour(Colour::GOLD)
+            .description(self.output_standings());
+
+        let teams = self.output_teams();
+        if !teams.is_empty() {
+            embed = embed.field("Teams", teams, false);
+        }
+        if let Some((ref name, elapsed)) = self.fastest {
+            embed = embed.field("Fastest answer", format!("{} in {}", name, format_duration(elapsed)), true);
+        }
+
+        embed.field("Questions", self.output_question_counts(), true)
+    }
+
+    // Lists every player's rank, score, correct answers and accuracy (unless it's hidden), one
+    // per line
+    // The top three places get a medal and are shown in bold
+    fn output_standings(&self) -> String {
+        let standings = self.standings();
+        if standings.is_empty() {
+            return String::from("Nobody answered any questions\n");
+        }
+
+        let mut output = String::new();
+        for standing in standings.iter().take(MAX_LISTED) {
+            let s = match MEDALS.get(standing.rank - 1) {
+                Some(medal) => format!("{} **{}. {} - {} points**", medal, standing.rank, standing.name, standing.score),
+                None => format!("{}. {} - {} points", standing.rank, standing.name, standing.score),
+            };
+            output.push_str(&s);
+            let s = if self.show_accuracy {
+                format!(" ({} correct, {}% accuracy)\n", standing.correct, standing.accuracy())
+            } else {
+                format!(" ({} correct)\n", standing.correct)
+            };
+            output.push_str(&s);
+        }
+        if standings.len() > MAX_LISTED {
+            let s = format!("...and {} more\n", standings.len() - MAX_LISTED);
             output.push_str(&s);
         }
 
         output
     }
+
+    // Lists every team's rank and total with its members' contributions, one per line
+    // Empty if nobody played on a team
+    fn output_teams(&self) -> String {
+        let mut output = String::new();
+        for (i, &(ref team, total, ref members)) in self.team_results().iter().enumerate() {
+            let contributions: Vec<String> = members
+                .iter()
+                .map(|&(ref name, score)| format!("{} {}", name, score))
+                .collect();
+            let s = format!("{}. {} - {} ({})\n", i + 1, team, total, contributions.join(", "));
+            output.push_str(&s);
+        }
+
+        output
+    }
+
+    // The fastest answer and question counts, as lines of text
+    fn output_stats(&self) -> String {
+        let mut output = String::new();
+        if let Some((ref name, elapsed)) = self.fastest {
+            let s = format!("Fastest answer: {} in {}\n", name, format_duration(elapsed));
+            output.push_str(&s);
+        }
+        let s = format!("Questions: {}\n", self.output_question_counts());
+        output.push_str(&s);
+
+        output
+    }
+
+    // Counts up the answered, skipped and unanswered questions as eg. "7 answered, 2 skipped"
+    fn output_question_counts(&self) -> String {
+        format!("{} answered, {} skipped, {} unanswered",
+                self.count_questions(Outcome::Answered),
+                self.count_questions(Outcome::Skipped),
+                self.count_questions(Outcome::Unanswered))
+    }
+}
+
+// Formats a duration in seconds to a tenth of a second, eg. "2.3s"
+fn format_duration(duration: Duration) -> String {
+    format!("{}.{}s", duration.as_secs(), duration.subsec_millis() / 100)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(id: u64, name: &str) -> User {
+        User {
+            id: UserId(id),
+            avatar: None,
+            bot: false,
+            discriminator: 1,
+            name: name.to_string(),
+        }
+    }
+
+    fn ranks(scores: &Scores) -> Vec<(usize, String)> {
+        scores.standings().into_iter().map(|s| (s.rank, s.name)).collect()
+    }
+
+    #[test]
+    fn standings_are_ordered_by_score() {
+        let mut scores = Scores::new();
+        scores.increase_score(user(1, "alice"), 1);
+        scores.increase_score(user(2, "bob"), 3);
+        scores.increase_score(user(3, "carol"), 2);
+
+        assert_eq!(ranks(&scores), vec![(1, "bob".to_string()), (2, "carol".to_string()), (3, "alice".to_string())]);
+    }
+
+    #[test]
+    fn tied_players_share_a_rank_and_skip_the_next() {
+        let mut scores = Scores::new();
+        scores.increase_score(user(1, "alice"), 5);
+        scores.increase_score(user(2, "bob"), 5);
+        scores.increase_score(user(3, "carol"), 2);
+        scores.increase_score(user(4, "dave"), 2);
+        scores.increase_score(user(5, "erin"), 1);
+
+        let ranked: Vec<usize> = scores.standings().iter().map(|s| s.rank).collect();
+        assert_eq!(ranked, vec![1, 1, 3, 3, 5]);
+    }
+
+    #[test]
+    fn ties_are_listed_by_name() {
+        let mut scores = Scores::new();
+        scores.increase_score(user(1, "zoe"), 2);
+        scores.increase_score(user(2, "adam"), 2);
+
+        assert_eq!(ranks(&scores), vec![(1, "adam".to_string()), (1, "zoe".to_string())]);
+    }
+
+    #[test]
+    fn attempts_without_points_are_ranked() {
+        let mut scores = Scores::new();
+        scores.increase_score(user(1, "alice"), 1);
+        scores.record_attempt(&user(2, "bob"), false);
+
+        let standings = scores.standings();
+        assert_eq!(standings[1].name, "bob");
+        assert_eq!(standings[1].rank, 2);
+        assert_eq!(standings[1].accuracy(), 0);
+    }
+
+    #[test]
+    fn accuracy_can_be_hidden() {
+        let mut scores = Scores::new();
+        scores.record_attempt(&user(1, "alice"), true);
+        assert!(scores.output_standings().contains("1 correct, 100% accuracy"));
+
+        scores.hide_accuracy();
+        assert!(scores.output_standings().contains("(1 correct)"));
+        assert!(!scores.output_standings().contains("accuracy"));
+    }
 }
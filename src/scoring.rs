@@ -0,0 +1,175 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use std::time::Duration;
+
+use logging;
+use question::Question;
+
+// Speed bonuses are measured against this window when a game has no time limit
+const DEFAULT_SPEED_WINDOW_SECS: u64 = 30;
+// The most points a speed bonus can give
+const MAX_SPEED_BONUS: u32 = 3;
+// The most points a streak bonus can give
+const MAX_STREAK_BONUS: u32 = 3;
+
+/// Everything a ScoringPolicy gets to know about a correct answer
+pub struct CorrectAnswer<'a> {
+    pub question: &'a Question,
+    /// How long after the question was posted it was answered
+    pub elapsed: Duration,
+    /// The game's time limit for each question, if it has one
+    pub time_limit: Option<Duration>,
+    /// The number of hints that were still unused when the question was answered. 0 if the
+    /// game has hints off.
+    pub hints_remaining: usize,
+    /// How many questions in a row the player has now answered, including this one
+    pub streak: u32,
+}
+
+/// The points awarded for an answer, split up by where they came from
+pub struct Breakdown {
+    parts: Vec<(u32, &'static str)>,
+}
+
+impl Breakdown {
+    fn new() -> Breakdown {
+        Breakdown { parts: Vec::new() }
+    }
+
+    // Adds some points to the breakdown. Zero point parts are left out.
+    fn add(&mut self, points: u32, reason: &'static str) {
+        if points > 0 {
+            self.parts.push((points, reason));
+        }
+    }
+
+    /// The total number of points
+    pub fn total(&self) -> u32 {
+        self.parts.iter().map(|&(points, _)| points).sum()
+    }
+}
+
+/// Formats the breakdown as eg. "1 base + 2 hard + 1 speed"
+impl Display for Breakdown {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let parts: Vec<String> = self.parts
+            .iter()
+            .map(|&(points, reason)| format!("{} {}", points, reason))
+            .collect();
+        write!(f, "{}", parts.join(" + "))
+    }
+}
+
+/// Decides how many points a correct answer is worth
+pub trait ScoringPolicy: Send + Sync {
+    fn score(&self, answer: &CorrectAnswer) -> Breakdown;
+}
+
+/// The built-in scoring policies a game can be played with
+///
+/// Every policy gives a bonus point for each hint left unused, unless the game has hints off.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scoring {
+    /// One point for every correct answer, plus the unused hint bonus
+    Classic,
+    /// Harder questions are worth more
+    Weighted,
+    /// Harder questions and quicker answers are worth more
+    Speed,
+    /// Harder questions are worth more, and answering several in a row earns a bonus
+    Streak,
+}
+
+impl Scoring {
+    /// Gets the policy which implements this kind of scoring
+    pub fn policy(&self) -> Box<dyn ScoringPolicy> {
+        match *self {
+            Scoring::Classic => Box::new(Classic),
+            Scoring::Weighted => Box::new(Weighted),
+            Scoring::Speed => Box::new(Speed),
+            Scoring::Streak => Box::new(Streak),
+        }
+    }
+}
+
+impl FromStr for Scoring {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Scoring, ()> {
+        match s.to_lowercase().as_str() {
+            "classic" => Ok(Scoring::Classic),
+            "weighted" => Ok(Scoring::Weighted),
+            "speed" => Ok(Scoring::Speed),
+            "streak" => Ok(Scoring::Streak),
+            _ => Err(()),
+        }
+    }
+}
+
+struct Classic;
+
+impl ScoringPolicy for Classic {
+    fn score(&self, answer: &CorrectAnswer) -> Breakdown {
+        let mut breakdown = Breakdown::new();
+        breakdown.add(1, "base");
+        add_hint_bonus(&mut breakdown, answer);
+        breakdown
+    }
+}
+
+struct Weighted;
+
+impl ScoringPolicy for Weighted {
+    fn score(&self, answer: &CorrectAnswer) -> Breakdown {
+        let mut breakdown = Breakdown::new();
+        add_difficulty(&mut breakdown, answer);
+        add_hint_bonus(&mut breakdown, answer);
+        breakdown
+    }
+}
+
+struct Speed;
+
+impl ScoringPolicy for Speed {
+    fn score(&self, answer: &CorrectAnswer) -> Breakdown {
+        let mut breakdown = Breakdown::new();
+        add_difficulty(&mut breakdown, answer);
+        add_hint_bonus(&mut breakdown, answer);
+
+        // The bonus shrinks evenly from its maximum to nothing over the time window
+        let window = answer.time_limit.unwrap_or_else(|| Duration::from_secs(DEFAULT_SPEED_WINDOW_SECS));
+        let window_ms = logging::millis(window).max(1);
+        let left_ms = window_ms.saturating_sub(logging::millis(answer.elapsed));
+        let bonus = (u64::from(MAX_SPEED_BONUS) * left_ms + window_ms / 2) / window_ms;
+        breakdown.add(bonus as u32, "speed");
+
+        breakdown
+    }
+}
+
+struct Streak;
+
+impl ScoringPolicy for Streak {
+    fn score(&self, answer: &CorrectAnswer) -> Breakdown {
+        let mut breakdown = Breakdown::new();
+        add_difficulty(&mut breakdown, answer);
+        add_hint_bonus(&mut breakdown, answer);
+        // Every answer in a row after the first adds a point, up to a limit
+        breakdown.add((answer.streak.saturating_sub(1)).min(MAX_STREAK_BONUS), "streak");
+        breakdown
+    }
+}
+
+// Easy questions are worth 1 point, medium 2 and hard 3
+fn add_difficulty(breakdown: &mut Breakdown, answer: &CorrectAnswer) {
+    match answer.question.difficulty.to_lowercase().as_str() {
+        "easy" => breakdown.add(1, "easy"),
+        "hard" => breakdown.add(3, "hard"),
+        "medium" => breakdown.add(2, "medium"),
+        _ => breakdown.add(1, "base"),
+    }
+}
+
+fn add_hint_bonus(breakdown: &mut Breakdown, answer: &CorrectAnswer) {
+    breakdown.add(answer.hints_remaining as u32, "unused hints");
+}
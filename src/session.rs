@@ -0,0 +1,764 @@
+use serenity::model::channel::Message;
+use serenity::model::id::{ ChannelId, GuildId, UserId };
+use serenity::model::user::User;
+use serenity::prelude::Mutex;
+use typemap::ShareMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use answer::{self, AnswerMode};
+use error::{Error, Result};
+use hint::{HintMode, Hints};
+use logging;
+use optionset::OptionSet;
+use present::{self, Post, QuestionPost, RevealPost, StandingsPost};
+use question::QuestionType;
+use questionset::QuestionSet;
+use scores::{Outcome, Scores};
+use scoring::{CorrectAnswer, ScoringPolicy};
+use skip::SkipThreshold;
+use teams::{self, TeamMode, Teams};
+use timer::{QuestionTimer, TimerEvent};
+
+// Every game gets its own id, so its log lines can be told apart from other games'
+static NEXT_GAME_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A TriviaSession holds the gamestate of a single game running in a single channel
+pub struct TriviaSession {
+    pub running: bool,
+    id: usize,
+    paused: bool,
+    question_set: QuestionSet,
+    channel: ChannelId,
+    guild: Option<GuildId>,
+    started_by: UserId,
+    scores: Scores,
+    skips: u32,
+    skip_threshold: SkipThreshold,
+    // Everyone who has answered or voted to skip in this game
+    active_players: HashSet<UserId>,
+    user_answered_list: HashMap<UserId, bool>,
+    user_skipped_list: HashMap<UserId, bool>,
+    // Every user's first pick on the current question, as (user name, option index)
+    guesses: Vec<(String, usize)>,
+    wrong_penalty: i32,
+    answer_mode: AnswerMode,
+    team_mode: TeamMode,
+    teams: Teams,
+    // Teams which have already picked an option on the current question
+    team_answered_list: HashMap<String, bool>,
+    // Players who have been told to join a team, so they're only told once
+    user_warned_list: HashMap<UserId, bool>,
+    scoring: Box<dyn ScoringPolicy>,
+    asked_at: Instant,
+    // The last player to answer correctly, and how many questions in a row they've answered
+    streak: Option<(UserId, u32)>,
+    time_limit: Option<Duration>,
+    timer: Option<QuestionTimer>,
+    hints: Option<Hints>,
+    hint_mode: HintMode,
+    hint_interval: Option<Duration>,
+    hint_timer: Option<QuestionTimer>,
+    // The prefix commands start with where the game is played
+    prefix: String,
+    data: Arc<Mutex<ShareMap>>,
+}
+
+impl TriviaSession {
+    /// Starts a new game in the channel given by the OptionSet, asking the questions in
+    /// `question_set`
+    ///
+    /// The first question is asked immediately. Nothing is sent to the channel if the set has
+    /// no questions.
+    ///
+    /// `teams` are the teams players have joined in the game's guild, and `data` is the bot's
+    /// shared data map, which is handed to question timers
+    pub fn start(optionset: &OptionSet,
+                 question_set: QuestionSet,
+                 teams: Teams,
+                 data: Arc<Mutex<ShareMap>>) -> Result<TriviaSession> {
+        let id = NEXT_GAME_ID.fetch_add(1, Ordering::Relaxed) + 1;
+        if question_set.get_current_question().is_none() {
+            return Err(Error::NoQuestions);
+        }
+
+        // Typed guesses can't be told apart from chat, so only correct ones are counted, which
+        // would make everyone's accuracy 100%
+        let mut scores = Scores::new();
+        if optionset.answer_mode == AnswerMode::Open {
+            scores.hide_accuracy();
+        }
+
+        let mut session = TriviaSession {
+            running: true,
+            id,
+            paused: false,
+            question_set,
+            channel: optionset.channel,
+            guild: optionset.guild,
+            started_by: optionset.started_by,
+            scores,
+            skips: 0,
+            skip_threshold: optionset.skip_threshold,
+            active_players: HashSet::new(),
+            user_answered_list: HashMap::new(),
+            user_skipped_list: HashMap::new(),
+            guesses: Vec::new(),
+            wrong_penalty: optionset.wrong_penalty as i32,
+            answer_mode: optionset.answer_mode,
+            team_mode: optionset.team_mode,
+            teams,
+            team_answered_list: HashMap::new(),
+            user_warned_list: HashMap::new(),
+            scoring: optionset.scoring.policy(),
+            asked_at: Instant::now(),
+            streak: None,
+            time_limit: optionset.time_limit,
+            timer: None,
+            hints: None,
+            hint_mode: optionset.hint_mode,
+            hint_interval: optionset.hint_interval,
+            hint_timer: None,
+            prefix: optionset.prefix.clone(),
+            data,
+        };
+
+        info!("game started {} user={} questions={}",
+              session.log_fields(), session.started_by.0, session.question_set.len());
+
+        //Tell the user we've started, and anything that had to change to find questions
+        session.say("Trivia Starting");
+        for adjustment in session.question_set.adjustments() {
+            session.say(adjustment);
+        }
+        //Then ask a question
+        session.ask_question();
+
+        Ok(session)
+    }
+
+    /// Stops the game and prints the final scores
+    pub fn stop(&mut self) {
+        if self.running {
+            info!("game stopped {} players={}", self.log_fields(), self.scores.results().len());
+            self.print_scores();
+            self.say("Trivia Stopping");
+            self.running = false;
+            self.cancel_timers();
+        }
+    }
+
+    /// Pauses the game on the current question
+    ///
+    /// The question's timers are stopped and answers are ignored until the game is resumed. If
+    /// `limit` is given, the game stops by itself once it has been paused for that long.
+    pub fn pause(&mut self, limit: Option<Duration>) {
+        if !self.running {
+            return;
+        }
+        if self.paused {
+            self.say("Trivia is already paused");
+            return;
+        }
+
+        info!("game paused {}", self.log_fields());
+        self.paused = true;
+        self.cancel_timers();
+        // The time limit timer isn't needed while paused, so the pause limit takes its place
+        if let Some(limit) = limit {
+            self.timer = Some(QuestionTimer::start(self.data.clone(),
+                                                   self.channel,
+                                                   self.question_set.position(),
+                                                   limit,
+                                                   TimerEvent::PauseLimit));
+            self.say(format!("Trivia paused. Use {}tresume within {} minutes to carry on.",
+                             self.prefix, (limit.as_secs() + 59) / 60));
+        } else {
+            self.say(format!("Trivia paused. Use {}tresume to carry on.", self.prefix));
+        }
+    }
+
+    /// Resumes a paused game by asking the current question again
+    ///
+    /// The question gets its full time limit back, and any hints already given stay used.
+    pub fn resume(&mut self) {
+        if !self.running {
+            return;
+        }
+        if !self.paused {
+            self.say("Trivia isn't paused");
+            return;
+        }
+
+        info!("game resumed {}", self.log_fields());
+        self.paused = false;
+        self.say("Trivia resuming");
+        self.post_question();
+        self.asked_at = Instant::now();
+        self.start_timers();
+    }
+
+    /// Stops the game once it has been paused for too long
+    ///
+    /// Nothing happens if the game was resumed, or has moved past `question_number`.
+    pub fn pause_expired(&mut self, question_number: usize) {
+        if !self.running || !self.paused || self.question_set.position() != question_number {
+            return;
+        }
+
+        info!("pause limit reached {}", self.log_fields());
+        self.say("Trivia was paused for too long");
+        self.stop();
+    }
+
+    /// The guild this game is played in, if any
+    pub fn guild(&self) -> Option<GuildId> {
+        self.guild
+    }
+
+    /// The player who started this game
+    pub fn started_by(&self) -> UserId {
+        self.started_by
+    }
+
+    /// The scores of every player in this game
+    pub fn scores(&self) -> &Scores {
+        &self.scores
+    }
+
+    /// Posts the scores so far without stopping the game
+    pub fn print_standings(&self) {
+        self.post(&Post::Standings(StandingsPost {
+            scores: &self.scores,
+            number: self.question_set.current_question_number(),
+            total: self.question_set.len(),
+        }));
+    }
+
+    /// Replaces the teams players are on, when someone joins or leaves a team mid-game
+    pub fn set_teams(&mut self, teams: Teams) {
+        self.teams = teams;
+    }
+
+    /// Skips the current question
+    pub fn vote_skip(&mut self, message: &Message) {
+        if self.paused {
+            self.say("Can't skip while trivia is paused");
+            return;
+        }
+        // Check if the user has already skipped
+        // If so, exit early and say nothing
+        if self.has_skipped(&message.author) {
+            return;
+        }
+        self.active_players.insert(message.author.id);
+        self.skips += 1;
+
+        let needed = self.skip_threshold.votes_needed(self.active_players.len());
+        if self.skips >= needed {
+            self.skip_question("Skipping question.");
+        } else {
+            self.say(format!("{} voted to skip. **Votes Needed: {}/{}**", message.author.name, self.skips, needed).as_str());
+        }
+    }
+
+    /// Skips the current question straight away, without a vote
+    pub fn force_skip(&mut self, message: &Message) {
+        if self.paused {
+            self.say("Can't skip while trivia is paused");
+            return;
+        }
+
+        self.skip_question(&format!("{} skipped the question.", message.author.name));
+    }
+
+    // Moves on from the current question without anyone answering it
+    fn skip_question(&mut self, announcement: &str) {
+        info!("question skipped {} votes={}", self.log_fields(), self.skips);
+        self.say(announcement);
+        self.streak = None;
+        self.scores.record_question(Outcome::Skipped);
+
+        self.next_question();
+        self.ask_question();
+    }
+
+    /// Method which runs whenever a new message is recieved in this session's channel.
+    ///
+    /// The text is checked to see if it is an answer
+    pub fn on_message(&mut self, message: Message) {
+        // Bots (including this one) never answer questions, and nobody can while paused
+        if !self.running || self.paused || message.author.bot {
+            return;
+        }
+
+        // In a team game, only players on a team can answer
+        let team = match self.team_mode {
+            TeamMode::Off => None,
+            TeamMode::Manual => self.teams.team_of(message.author.id).map(|team| team.to_string()),
+            TeamMode::Roles => teams::team_from_roles(&message),
+        };
+        if self.team_mode != TeamMode::Off {
+            match team {
+                Some(ref team) => self.scores.set_team(message.author.id, team.clone()),
+                None => {
+                    if self.is_answer_attempt(message.content.as_str()) && !self.has_been_warned(&message.author) {
+                        self.say(match self.team_mode {
+                            TeamMode::Roles => format!("{}, you need a role starting with \"Team\" to answer", message.author.name),
+                            _ => format!("{}, join a team with {}tteam join <name> to answer", message.author.name, self.prefix),
+                        });
+                    }
+                    return;
+                }
+            }
+        }
+
+        let got_it = match self.answer_mode {
+            AnswerMode::Choice => {
+                let choice = match self.get_choice(message.content.as_str()) {
+                    Some(choice) => choice,
+                    None => return,
+                };
+                self.active_players.insert(message.author.id);
+
+                //Only a team's first pick counts, so teammates can't cover every option
+                if let Some(ref team) = team {
+                    if self.has_team_answered(team) {
+                        return;
+                    }
+                }
+
+                //Check if the answer is correct
+                let correct = self.check_answer(message.content.as_str());
+                //Check if this is the users first guess
+                let has_answered = self.has_answered(&message.author);
+                if !has_answered {
+                    //Remember the pick for the summary at the end of the question
+                    self.guesses.push((message.author.name.clone(), choice));
+                    self.scores.record_attempt(&message.author, correct);
+
+                    //Wrong picks cost points when negative marking is on
+                    if !correct && self.wrong_penalty > 0 {
+                        let penalty = self.wrong_penalty;
+                        self.scores.decrease_score(message.author.clone(), penalty);
+                    }
+                }
+                //If the answer is correct AND it is the user's first guess, they got the
+                //question right
+                correct && !has_answered
+            }
+            //Typed answers can be guessed as many times as the user likes, so only the
+            //correct one counts as an attempt
+            AnswerMode::Open => {
+                //Anyone typing during a typed answer game is taking part
+                self.active_players.insert(message.author.id);
+
+                //True/False questions only have two answers, so each user only gets one guess
+                //at them, or they could type both
+                if self.is_boolean_question() {
+                    if self.get_choice(message.content.as_str()).is_none() || self.has_answered(&message.author) {
+                        return;
+                    }
+                    let correct = self.check_typed_answer(message.content.as_str());
+                    self.scores.record_attempt(&message.author, correct);
+                    correct
+                } else {
+                    let correct = self.check_typed_answer(message.content.as_str());
+                    if correct {
+                        self.scores.record_attempt(&message.author, true);
+                    }
+                    correct
+                }
+            }
+        };
+
+        if got_it {
+            //Keep the user's streak going, or start a new one
+            let streak = match self.streak {
+                Some((user, streak)) if user == message.author.id => streak + 1,
+                _ => 1,
+            };
+            self.streak = Some((message.author.id, streak));
+
+            //Work out what the answer is worth
+            let elapsed = self.asked_at.elapsed();
+            let breakdown = match self.question_set.get_current_question() {
+                Some(q) => self.scoring.score(&CorrectAnswer {
+                    question: q,
+                    elapsed,
+                    time_limit: self.time_limit,
+                    hints_remaining: self.unused_hints(),
+                    streak,
+                }),
+                None => return,
+            };
+
+            //Congradulate the user, and their team if they're on one
+            let name = match team {
+                Some(ref team) => format!("{} ({})", message.author.name, team),
+                None => message.author.name.clone(),
+            };
+            if let Some(q) = self.question_set.get_current_question() {
+                self.post(&Post::Reveal(RevealPost {
+                    question: q,
+                    answered_by: Some(name),
+                    points: Some(&breakdown),
+                    show_label: self.answer_mode == AnswerMode::Choice,
+                }));
+            }
+
+            info!("question answered {} user={} points={} elapsed_ms={}",
+                  self.log_fields(), message.author.id.0, breakdown.total(), logging::millis(elapsed));
+
+            //Increase the user's score
+            self.scores.record_time(&message.author, elapsed);
+            self.scores.increase_score(message.author, breakdown.total() as i32);
+            self.scores.record_question(Outcome::Answered);
+            self.mark_answered(true);
+
+            self.next_question();
+            self.ask_question();
+        }
+    }
+
+    // Checks if the current question is a True/False question
+    fn is_boolean_question(&self) -> bool {
+        self.question_set
+            .get_current_question()
+            .is_some_and(|q| q.question_type == QuestionType::Boolean)
+    }
+
+    // Checks if a typed guess is close enough to the current question's answer
+    // True/False questions can also be answered the same way as in choice mode
+    fn check_typed_answer(&self, message: &str) -> bool {
+        match self.question_set.get_current_question() {
+            Some(q) => {
+                answer::is_match(message, &q.answer, &q.options) ||
+                    (q.question_type == QuestionType::Boolean && q.choice(message) == Some(q.answer_index))
+            }
+            None => false,
+        }
+    }
+
+    /// Ends the current question when its time limit has expired
+    ///
+    /// The correct answer is revealed and the next question is asked. `question_number` is the
+    /// question the timer was started for; if the game has already moved past it, nothing happens.
+    pub fn time_up(&mut self, question_number: usize) {
+        if !self.running || self.paused || self.question_set.position() != question_number {
+            return;
+        }
+
+        match self.question_set.get_current_question() {
+            Some(q) => self.post(&Post::Reveal(RevealPost {
+                question: q,
+                answered_by: None,
+                points: None,
+                show_label: self.answer_mode == AnswerMode::Choice,
+            })),
+            None => return,
+        }
+        info!("question timed out {}", self.log_fields());
+        self.mark_answered(false);
+        self.streak = None;
+        self.scores.record_question(Outcome::Unanswered);
+
+        self.next_question();
+        self.ask_question();
+    }
+
+    /// Gives the next hint for the current question, if there are any left
+    pub fn give_hint(&mut self) {
+        if self.paused {
+            self.say("Can't give a hint while trivia is paused");
+            return;
+        }
+        if self.hint_mode == HintMode::Off {
+            self.say("Hints are off in this game");
+            return;
+        }
+        let hint = match (self.question_set.get_current_question(), self.hints.as_mut()) {
+            (Some(q), Some(hints)) => hints.next_hint(q),
+            _ => return,
+        };
+
+        match hint {
+            Some(hint) => self.say(format!("Hint:\n{}", hint)),
+            None => self.say("There are no hints left for this question"),
+        }
+    }
+
+    /// Gives a hint when the automatic hint timer for a question has run out
+    ///
+    /// Another hint is scheduled for as long as the question has hints left. If the game has
+    /// already moved past `question_number`, nothing happens.
+    pub fn auto_hint(&mut self, question_number: usize) {
+        if !self.running || self.paused || self.question_set.position() != question_number {
+            return;
+        }
+
+        if self.hints_remaining() > 0 {
+            self.give_hint();
+        }
+        self.start_hint_timer();
+    }
+
+    fn next_question(&mut self) {
+        self.cancel_timers();
+        self.post_guess_summary();
+        self.question_set.next_question();
+
+        self.user_answered_list.clear();
+        self.user_skipped_list.clear();
+        self.skips = 0;
+        self.team_answered_list.clear();
+        self.guesses.clear();
+    }
+
+    // Posts how everyone voted on the current question, if anyone picked an option
+    fn post_guess_summary(&self) {
+        let q = match self.question_set.get_current_question() {
+            Some(q) if !self.guesses.is_empty() => q,
+            _ => return,
+        };
+
+        let mut summary = String::from("How everyone voted:\n");
+        for (i, option) in q.options.iter().enumerate() {
+            let voters: Vec<&str> = self.guesses
+                .iter()
+                .filter(|&&(_, choice)| choice == i)
+                .map(|&(ref name, _)| name.as_str())
+                .collect();
+            let mark = if i == q.answer_index { " ✅" } else { "" };
+
+            let line = if voters.is_empty() {
+                format!("{}. {} - 0{}\n", q.label(i), option, mark)
+            } else {
+                format!("{}. {} - {} ({}){}\n", q.label(i), option, voters.len(), voters.join(", "), mark)
+            };
+            summary.push_str(&line);
+        }
+
+        let wrong = self.guesses.iter().filter(|&&(_, choice)| choice != q.answer_index).count();
+        if self.wrong_penalty > 0 && wrong > 0 {
+            summary.push_str(&format!("Wrong picks cost {} points each\n", self.wrong_penalty));
+        }
+
+        self.say(summary);
+    }
+
+    // Works out which of the current question's options a message picks, if any
+    fn get_choice(&self, message: &str) -> Option<usize> {
+        self.question_set
+            .get_current_question()
+            .and_then(|q| q.choice(message))
+    }
+
+    // Posts the final results to the session's channel
+    fn print_scores(&self) {
+        self.post(&Post::Results(&self.scores));
+    }
+
+    // Checks AND sets if a user has answered the question
+    // Returns true if the user has already answered, and false if they haven't.
+    // After this function is run, the passed user will not be able to answer a question again
+    // until the answered list is cleared
+    fn has_answered(&mut self, user: &User) -> bool {
+        //Mark that the given user has attempted to answer the question
+        let answered = self.user_answered_list.insert(user.id, true);
+
+        //If answered is a Some value, the user already answered. Return true.
+        //If the value is None, this is the users first answer. Return false, the user HASN'T
+        //answered yet
+        match answered {
+            Some(_) => true,
+            None => false,
+        }
+
+        //After the execution of this function, the user will be marked as having attempted to
+        //answer the question
+    }
+
+    // Checks AND sets if a team has picked an option on the current question
+    // Returns true if the team has already picked one
+    fn has_team_answered(&mut self, team: &str) -> bool {
+        self.team_answered_list.insert(team.to_string(), true).is_some()
+    }
+
+    // Checks AND sets if a player without a team has been told how to join one
+    // Returns true if they've already been told
+    fn has_been_warned(&mut self, user: &User) -> bool {
+        self.user_warned_list.insert(user.id, true).is_some()
+    }
+
+    // Checks if a message looks like an answer to the current question, rather than chat
+    fn is_answer_attempt(&self, message: &str) -> bool {
+        match self.answer_mode {
+            AnswerMode::Choice => self.get_choice(message).is_some(),
+            AnswerMode::Open => self.check_typed_answer(message),
+        }
+    }
+
+    //Checks AND sets if a user has skipped the current question
+    // Returns true if the user has already voted to skip, and false if they haven't.
+    // After this functionis run, the passed user will not be able to skip the current question
+    // again until the user_skipped_list has been cleared
+    fn has_skipped(&mut self, user: &User) -> bool {
+        let skipped = self.user_skipped_list.insert(user.id, true);
+
+        match skipped {
+            Some(_) => true,
+            None    => false,
+        }
+
+    }
+
+    // Sends a message to the session's channel with the current question
+    // When no more questions are available, this method calls the stop() method
+    fn ask_question(&mut self) {
+        // If question is false, there was no question to ask
+        let question = self.post_question();
+
+        // Stop if we don't have any more questions to ask
+        if !question {
+            self.stop();
+        } else {
+            self.asked_at = Instant::now();
+            let answer_mode = self.answer_mode;
+            self.hints = self.question_set.get_current_question().map(|q| Hints::new(q, answer_mode));
+            self.start_timers();
+        }
+    }
+
+    // Posts the current question to the session's channel
+    // Returns false if there are no questions left to post
+    fn post_question(&self) -> bool {
+        match self.question_set.get_current_question() {
+            Some(q) => {
+                // Typed answers keep the options hidden, except for True/False which has to
+                // say that it is one
+                self.post(&Post::Question(QuestionPost {
+                    question: q,
+                    number: self.question_set.current_question_number(),
+                    total: self.question_set.len(),
+                    time_limit: self.time_limit,
+                    show_options: self.answer_mode == AnswerMode::Choice ||
+                        q.question_type == QuestionType::Boolean,
+                }));
+                if logging::log_answers() {
+                    debug!("question asked {} answer={:?}", self.log_fields(), q.answer);
+                }
+                true
+            }
+            None => {
+                self.say("Out of questions");
+                false
+            }
+        }
+    }
+
+    // Starts the countdowns for the current question, if this game has a time limit or
+    // automatic hints
+    fn start_timers(&mut self) {
+        self.cancel_timers();
+
+        if let Some(limit) = self.time_limit {
+            self.timer = Some(QuestionTimer::start(self.data.clone(),
+                                                   self.channel,
+                                                   self.question_set.position(),
+                                                   limit,
+                                                   TimerEvent::TimeUp));
+        }
+        self.start_hint_timer();
+    }
+
+    // Schedules the next automatic hint, if this game has them and the question has hints left
+    fn start_hint_timer(&mut self) {
+        self.hint_timer = None;
+
+        if let Some(interval) = self.hint_interval {
+            if self.hints_remaining() > 0 {
+                self.hint_timer = Some(QuestionTimer::start(self.data.clone(),
+                                                            self.channel,
+                                                            self.question_set.position(),
+                                                            interval,
+                                                            TimerEvent::Hint));
+            }
+        }
+    }
+
+    // Stops the countdowns for the current question, if there are any
+    fn cancel_timers(&mut self) {
+        if let Some(timer) = self.timer.take() {
+            timer.cancel();
+        }
+        if let Some(timer) = self.hint_timer.take() {
+            timer.cancel();
+        }
+    }
+
+    // The number of hints left for the current question
+    fn hints_remaining(&self) -> usize {
+        match self.hints {
+            Some(ref hints) => hints.remaining(),
+            None => 0,
+        }
+    }
+
+    // The number of hints left for the current question which count towards its score
+    // Games played with hints off don't count them, so they don't favour questions which have
+    // more of them
+    fn unused_hints(&self) -> usize {
+        match self.hint_mode {
+            HintMode::Off => 0,
+            HintMode::Manual | HintMode::Auto => self.hints_remaining(),
+        }
+    }
+
+    // Records whether the current question was answered before moving on from it
+    fn mark_answered(&mut self, answered: bool) {
+        if let Some(q) = self.question_set.get_current_question_mut() {
+            q.answered = answered;
+        }
+    }
+
+    // Checks if a given string matches the current question's answer
+    fn check_answer(&mut self, message: &str) -> bool {
+        match self.question_set.get_current_question() {
+            Some(q) => {
+                //Check if the message picks the answer
+                q.choice(message) == Some(q.answer_index)
+            }
+            None => false,
+        }
+    }
+
+    // The fields every log line about this game starts with
+    fn log_fields(&self) -> String {
+        let guild = match self.guild {
+            Some(guild) => guild.0.to_string(),
+            None => String::from("none"),
+        };
+        format!("game={} guild={} channel={} question={}",
+                self.id, guild, self.channel.0, self.question_set.current_question_number())
+    }
+
+    // Sends a post to the session's channel, as an embed where the bot is allowed to
+    // A post that fails to send is logged rather than stopping the game
+    fn post(&self, post: &Post) {
+        if let Err(why) = present::send(self.channel, post) {
+            error!("message send failed {} error={:?}", self.log_fields(), why);
+        }
+    }
+
+    // Sends a message to the session's channel
+    // A message that fails to send is logged rather than stopping the game
+    fn say<T: Display>(&self, message: T) {
+        if let Err(why) = self.channel.say(format!("{}", message)) {
+            error!("message send failed {} error={:?}", self.log_fields(), Error::from(why));
+        }
+    }
+}
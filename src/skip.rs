@@ -0,0 +1,87 @@
+use std::str::FromStr;
+
+/// How many votes it takes to skip a question
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SkipThreshold {
+    /// A fixed number of votes
+    Votes(u32),
+    /// A percentage of the players who have taken part in the game so far
+    Percent(u32),
+}
+
+impl SkipThreshold {
+    /// The number of votes needed to skip when `active_players` have taken part in the game
+    ///
+    /// A number of votes is only lowered to the number of active players, and never below two,
+    /// since the first voter is often the only player so far. At least one vote is always needed.
+    pub fn votes_needed(&self, active_players: usize) -> u32 {
+        let active = active_players.max(1) as u32;
+        let needed = match *self {
+            SkipThreshold::Votes(votes) => votes.min(active.max(2)),
+            // Round up, so half of three players is two votes
+            SkipThreshold::Percent(percent) => (active * percent).div_ceil(100),
+        };
+
+        needed.max(1)
+    }
+}
+
+/// Parses a threshold written as a number of votes like "3", or a percentage like "50%"
+impl FromStr for SkipThreshold {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<SkipThreshold, ()> {
+        if let Some(percent) = s.strip_suffix('%') {
+            match percent.parse::<u32>() {
+                Ok(percent) if percent > 0 && percent <= 100 => Ok(SkipThreshold::Percent(percent)),
+                _ => Err(()),
+            }
+        } else {
+            match s.parse::<u32>() {
+                Ok(votes) if votes > 0 => Ok(SkipThreshold::Votes(votes)),
+                _ => Err(()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn votes_are_capped_to_the_active_players_but_not_below_two() {
+        assert_eq!(SkipThreshold::Votes(3).votes_needed(5), 3);
+        assert_eq!(SkipThreshold::Votes(3).votes_needed(2), 2);
+        assert_eq!(SkipThreshold::Votes(3).votes_needed(1), 2);
+        assert_eq!(SkipThreshold::Votes(3).votes_needed(0), 2);
+    }
+
+    #[test]
+    fn at_least_one_vote_is_needed() {
+        assert_eq!(SkipThreshold::Votes(1).votes_needed(0), 1);
+        assert_eq!(SkipThreshold::Percent(50).votes_needed(0), 1);
+        assert_eq!(SkipThreshold::Percent(1).votes_needed(4), 1);
+    }
+
+    #[test]
+    fn percentages_round_up() {
+        assert_eq!(SkipThreshold::Percent(50).votes_needed(3), 2);
+        assert_eq!(SkipThreshold::Percent(50).votes_needed(4), 2);
+        assert_eq!(SkipThreshold::Percent(100).votes_needed(7), 7);
+    }
+
+    #[test]
+    fn parses_votes_and_percentages() {
+        assert_eq!("3".parse(), Ok(SkipThreshold::Votes(3)));
+        assert_eq!("50%".parse(), Ok(SkipThreshold::Percent(50)));
+        assert_eq!("100%".parse(), Ok(SkipThreshold::Percent(100)));
+    }
+
+    #[test]
+    fn rejects_zero_and_garbage() {
+        for text in &["0", "0%", "101%", "%", "", "-1", "three", "5 %"] {
+            assert_eq!(text.parse::<SkipThreshold>(), Err(()), "{:?} was accepted", text);
+        }
+    }
+}
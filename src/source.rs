@@ -0,0 +1,80 @@
+use error::Result;
+use fuzzy::edit_distance;
+use optionset::OptionSet;
+use questionset::QuestionSet;
+
+/// A category of questions offered by a QuestionSource
+#[derive(Clone, Debug)]
+pub struct Category {
+    /// The value to put in `OptionSet::category` to ask questions from this category
+    pub id: String,
+    pub name: String,
+    /// The number of questions in the category, if the source knows it
+    pub count: Option<u32>,
+}
+
+/// The result of looking up a category by name
+pub enum CategoryMatch {
+    Found(Category),
+    /// Several categories matched equally well
+    Ambiguous(Vec<Category>),
+    /// Nothing matched. Holds the closest category name, if any came close.
+    Unknown(Option<Category>),
+}
+
+/// Somewhere trivia questions can be pulled from
+///
+/// The QuestionCache asks its source for questions when a game starts without enough ready,
+/// and to keep its pools topped up. It pulls the category list once, on its own thread.
+pub trait QuestionSource: Send + Sync {
+    /// Produces a QuestionSet based on the given OptionSet.
+    fn get_question_set(&self, options: &OptionSet) -> Result<QuestionSet>;
+
+    /// Lists the categories questions can be asked from
+    fn categories(&self) -> Result<Vec<Category>>;
+}
+
+/// Looks up a category by its name
+///
+/// An exact name wins, then a category whose name contains `query` (so "film" finds
+/// "Entertainment: Film"). Case is ignored throughout.
+pub fn find_category(categories: &[Category], query: &str) -> CategoryMatch {
+    let query = query.trim().to_lowercase();
+
+    if let Some(category) = categories.iter().find(|c| c.name.to_lowercase() == query) {
+        return CategoryMatch::Found(category.clone());
+    }
+
+    let mut partial: Vec<Category> = categories
+        .iter()
+        .filter(|c| c.name.to_lowercase().contains(&query))
+        .cloned()
+        .collect();
+
+    match partial.len() {
+        0 => CategoryMatch::Unknown(closest_category(categories, &query)),
+        1 => CategoryMatch::Found(partial.remove(0)),
+        _ => CategoryMatch::Ambiguous(partial),
+    }
+}
+
+// Finds the category whose name, or one word of whose name, is the fewest edits away from
+// the query. Categories needing more edits than half the query's length aren't suggested.
+fn closest_category(categories: &[Category], query: &str) -> Option<Category> {
+    categories
+        .iter()
+        .map(|c| {
+            let name = c.name.to_lowercase();
+            let distance = name
+                .split(|ch: char| !ch.is_alphanumeric())
+                .filter(|word| !word.is_empty())
+                .map(|word| edit_distance(word, query))
+                .chain(Some(edit_distance(&name, query)))
+                .min()
+                .unwrap_or(usize::MAX);
+            (distance, c)
+        })
+        .filter(|&(distance, _)| distance <= query.chars().count() / 2)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, c)| c.clone())
+}
@@ -0,0 +1,279 @@
+use std::time::Duration;
+
+use answer::AnswerMode;
+use config::{Settings, MAX_QUESTIONS, MAX_TIME_LIMIT};
+use fuzzy::edit_distance;
+use hint::HintMode;
+use question::QuestionType;
+use scoring::Scoring;
+use skip::SkipThreshold;
+use teams::TeamMode;
+
+// The most points a wrong pick can cost
+const MAX_PENALTY: u32 = 10;
+
+// Every option the start command takes, with the other names it can be given by
+const OPTIONS: [(&str, &[&str]); 11] = [
+    ("n", &["questions"]),
+    ("diff", &["difficulty"]),
+    ("cat", &["category"]),
+    ("type", &[]),
+    ("time", &[]),
+    ("mode", &[]),
+    ("scoring", &[]),
+    ("teams", &[]),
+    ("skip", &[]),
+    ("penalty", &[]),
+    ("hints", &[]),
+];
+
+/// The options a game is started with, before its category has been looked up
+pub struct StartArgs {
+    pub number_of_questions: u32,
+    /// Empty for any difficulty
+    pub difficulty: String,
+    /// The category as the player wrote it. Empty for any category.
+    pub category: String,
+    pub question_type: Option<QuestionType>,
+    pub time_limit: Option<Duration>,
+    pub answer_mode: AnswerMode,
+    pub scoring: Scoring,
+    pub team_mode: TeamMode,
+    pub skip_threshold: SkipThreshold,
+    pub wrong_penalty: u32,
+    pub hint_mode: HintMode,
+}
+
+impl StartArgs {
+    /// Parses the start command's arguments, written as `name=value` pairs in any order
+    ///
+    /// Anything not given comes from `settings`. The category's value runs up to the next
+    /// option, so it can have spaces in it (eg. `cat=video games n=5`). Returns a message saying
+    /// what's wrong if an option or its value isn't recognised.
+    pub fn parse(text: &str, settings: &Settings) -> Result<StartArgs, String> {
+        let mut args = StartArgs {
+            number_of_questions: settings.questions,
+            difficulty: settings.difficulty.clone(),
+            category: String::new(),
+            question_type: Some(QuestionType::Multiple),
+            time_limit: settings.time_limit,
+            answer_mode: AnswerMode::Choice,
+            scoring: Scoring::Classic,
+            team_mode: TeamMode::Off,
+            skip_threshold: settings.skip_threshold,
+            wrong_penalty: 0,
+            hint_mode: HintMode::Manual,
+        };
+
+        for (name, value) in split_pairs(text)? {
+            args.set(name, &value)?;
+        }
+
+        Ok(args)
+    }
+
+    // Checks and sets a single option
+    fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let lower = value.to_lowercase();
+        match name {
+            "n" => match value.parse::<u32>() {
+                Ok(n) if n > 0 && n <= MAX_QUESTIONS => self.number_of_questions = n,
+                _ => return Err(format!("n must be a number of questions from 1 to {}, not \"{}\"", MAX_QUESTIONS, value)),
+            },
+            "diff" => match lower.as_str() {
+                "easy" | "medium" | "hard" => self.difficulty = lower.clone(),
+                // "any" difficulty is asked for by leaving the difficulty out
+                "any" => self.difficulty = String::new(),
+                _ => return Err(format!("diff must be easy, medium, hard or any, not \"{}\"", value)),
+            },
+            "cat" => self.category = value.to_string(),
+            "type" => match lower.as_str() {
+                "mixed" => self.question_type = None,
+                _ => match lower.parse::<QuestionType>() {
+                    Ok(question_type) => self.question_type = Some(question_type),
+                    Err(_) => return Err(format!("type must be multiple, boolean or mixed, not \"{}\"", value)),
+                },
+            },
+            "time" => match lower.trim_end_matches('s').parse::<u64>() {
+                Ok(0) => self.time_limit = None,
+                Ok(seconds) if seconds <= MAX_TIME_LIMIT => self.time_limit = Some(Duration::from_secs(seconds)),
+                _ => return Err(format!("time must be a number of seconds up to {} like 20s, or 0s for no limit, not \"{}\"",
+                                        MAX_TIME_LIMIT, value)),
+            },
+            "mode" => match value.parse::<AnswerMode>() {
+                Ok(mode) => self.answer_mode = mode,
+                Err(_) => return Err(format!("mode must be choice or open, not \"{}\"", value)),
+            },
+            "scoring" => match value.parse::<Scoring>() {
+                Ok(scoring) => self.scoring = scoring,
+                Err(_) => return Err(format!("scoring must be classic, weighted, speed or streak, not \"{}\"", value)),
+            },
+            "teams" => match value.parse::<TeamMode>() {
+                Ok(team_mode) => self.team_mode = team_mode,
+                Err(_) => return Err(format!("teams must be solo, teams or roleteams, not \"{}\"", value)),
+            },
+            "skip" => match value.parse::<SkipThreshold>() {
+                Ok(threshold) => self.skip_threshold = threshold,
+                Err(_) => return Err(format!("skip must be a number of votes like 2, or a percentage like 50%, not \"{}\"", value)),
+            },
+            "penalty" => match value.parse::<u32>() {
+                Ok(points) if points <= MAX_PENALTY => self.wrong_penalty = points,
+                _ => return Err(format!("penalty must be a number of points from 0 to {}, not \"{}\"", MAX_PENALTY, value)),
+            },
+            "hints" => match value.parse::<HintMode>() {
+                Ok(hint_mode) => self.hint_mode = hint_mode,
+                Err(_) => return Err(format!("hints must be off, manual or auto, not \"{}\"", value)),
+            },
+            _ => unreachable!("split_pairs only gives known options"),
+        }
+
+        Ok(())
+    }
+}
+
+// Splits the arguments up into (option, value) pairs, using each option's main name
+// Words without an "=" carry on the value before them, which only the category allows
+fn split_pairs(text: &str) -> Result<Vec<(&'static str, String)>, String> {
+    let mut pairs: Vec<(&'static str, String)> = Vec::new();
+    for word in text.split_whitespace() {
+        if let Some(equals) = word.find('=') {
+            let name = option_name(&word[..equals])?;
+            if pairs.iter().any(|&(given, _)| given == name) {
+                return Err(format!("{} is given more than once", name));
+            }
+            pairs.push((name, word[equals + 1..].to_string()));
+            continue;
+        }
+
+        match pairs.last_mut() {
+            Some(&mut ("cat", ref mut value)) => {
+                value.push(' ');
+                value.push_str(word);
+            }
+            _ => return Err(format!("\"{}\" isn't an option. Options are written like n=10", word)),
+        }
+    }
+
+    for &(name, ref value) in &pairs {
+        if value.is_empty() {
+            return Err(format!("{} needs a value after the =", name));
+        }
+    }
+
+    Ok(pairs)
+}
+
+// Finds the main name of an option, suggesting the closest one if it's misspelled
+fn option_name(name: &str) -> Result<&'static str, String> {
+    let name = name.to_lowercase();
+    for &(option, aliases) in OPTIONS.iter() {
+        if option == name || aliases.contains(&name.as_str()) {
+            return Ok(option);
+        }
+    }
+
+    let closest = OPTIONS
+        .iter()
+        .flat_map(|&(option, aliases)| ::std::iter::once(option).chain(aliases.iter().cloned()))
+        .min_by_key(|candidate| edit_distance(&name, candidate));
+    match closest {
+        Some(closest) if edit_distance(&name, closest) <= 2 => {
+            Err(format!("Unknown option \"{}\". Did you mean \"{}\"?", name, closest))
+        }
+        _ => Err(format!("Unknown option \"{}\"", name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<StartArgs, String> {
+        StartArgs::parse(text, &Settings::default())
+    }
+
+    #[test]
+    fn options_not_given_come_from_the_settings() {
+        let settings = Settings {
+            questions: 15,
+            difficulty: String::from("hard"),
+            time_limit: Some(Duration::from_secs(30)),
+            skip_threshold: SkipThreshold::Votes(2),
+            ..Settings::default()
+        };
+
+        let args = StartArgs::parse("", &settings).unwrap();
+        assert_eq!(args.number_of_questions, 15);
+        assert_eq!(args.difficulty, "hard");
+        assert_eq!(args.category, "");
+        assert_eq!(args.question_type, Some(QuestionType::Multiple));
+        assert_eq!(args.time_limit, Some(Duration::from_secs(30)));
+        assert_eq!(args.skip_threshold, SkipThreshold::Votes(2));
+    }
+
+    #[test]
+    fn options_can_be_given_in_any_order_and_by_alias() {
+        let args = parse("difficulty=EASY questions=20 type=mixed time=20s").unwrap();
+        assert_eq!(args.number_of_questions, 20);
+        assert_eq!(args.difficulty, "easy");
+        assert_eq!(args.question_type, None);
+        assert_eq!(args.time_limit, Some(Duration::from_secs(20)));
+    }
+
+    #[test]
+    fn the_category_can_have_spaces() {
+        let args = parse("cat=video games n=5").unwrap();
+        assert_eq!(args.category, "video games");
+        assert_eq!(args.number_of_questions, 5);
+    }
+
+    #[test]
+    fn only_the_category_takes_extra_words() {
+        assert!(parse("n=5 10").is_err());
+        assert!(parse("history").is_err());
+    }
+
+    #[test]
+    fn options_can_only_be_given_once() {
+        assert_eq!(split_pairs("n=5 questions=6"), Err(String::from("n is given more than once")));
+    }
+
+    #[test]
+    fn options_need_a_value() {
+        assert_eq!(split_pairs("n="), Err(String::from("n needs a value after the =")));
+    }
+
+    #[test]
+    fn option_names_are_looked_up_by_alias() {
+        assert_eq!(option_name("N"), Ok("n"));
+        assert_eq!(option_name("category"), Ok("cat"));
+        assert_eq!(option_name("difficulty"), Ok("diff"));
+    }
+
+    #[test]
+    fn misspelled_options_get_a_suggestion() {
+        assert_eq!(option_name("scorng"), Err(String::from("Unknown option \"scorng\". Did you mean \"scoring\"?")));
+        assert_eq!(option_name("colour"), Err(String::from("Unknown option \"colour\"")));
+    }
+
+    #[test]
+    fn values_are_checked() {
+        let mut args = parse("").unwrap();
+        assert!(args.set("n", "0").is_err());
+        assert!(args.set("n", &(MAX_QUESTIONS + 1).to_string()).is_err());
+        assert!(args.set("diff", "impossible").is_err());
+        assert!(args.set("time", &format!("{}s", MAX_TIME_LIMIT + 1)).is_err());
+        assert!(args.set("penalty", &(MAX_PENALTY + 1).to_string()).is_err());
+        assert!(args.set("hints", "sometimes").is_err());
+        assert!(args.set("skip", "0").is_err());
+
+        args.set("diff", "any").unwrap();
+        assert_eq!(args.difficulty, "");
+        args.set("time", "0s").unwrap();
+        assert_eq!(args.time_limit, None);
+        args.set("hints", "off").unwrap();
+        assert_eq!(args.hint_mode, HintMode::Off);
+        args.set("penalty", "2").unwrap();
+        assert_eq!(args.wrong_penalty, 2);
+    }
+}
@@ -0,0 +1,34 @@
+extern crate serde;
+extern crate serde_json;
+
+use self::serde::de::DeserializeOwned;
+use self::serde::Serialize;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// Reads a value from the JSON file at `path`
+///
+/// A missing file gives the default value, so the file can be created by the first save.
+pub fn load_json<T: DeserializeOwned + Default>(path: &Path) -> io::Result<T> {
+    match File::open(path) {
+        Ok(file) => serde_json::from_reader(BufReader::new(file))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Writes a value as JSON to a temporary file and moves it over the file at `path`, so a crash
+/// mid-write can't corrupt what was saved before
+pub fn save_json<T: Serialize>(path: &Path, value: &T) -> io::Result<()> {
+    let temp_path = path.with_extension("tmp");
+    {
+        let mut writer = BufWriter::new(File::create(&temp_path)?);
+        serde_json::to_writer(&mut writer, value)
+            .map_err(io::Error::other)?;
+        writer.flush()?;
+    }
+
+    fs::rename(&temp_path, path)
+}
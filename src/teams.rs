@@ -0,0 +1,136 @@
+use serenity::model::channel::Message;
+use serenity::model::id::UserId;
+use serenity::model::user::User;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+// Roles whose name starts with this put their members on a team, when teams come from roles
+const TEAM_ROLE_PREFIX: &str = "team";
+// The longest name a team can be given
+const MAX_TEAM_NAME_LENGTH: usize = 32;
+
+/// How players are put on teams
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TeamMode {
+    /// Everyone plays for themselves
+    Off,
+    /// Players join teams with `.tteam join <name>`
+    Manual,
+    /// Players are on the team named by their Discord role starting with "Team"
+    Roles,
+}
+
+impl FromStr for TeamMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<TeamMode, ()> {
+        match s.to_lowercase().as_str() {
+            "solo" => Ok(TeamMode::Off),
+            "teams" => Ok(TeamMode::Manual),
+            "roleteams" => Ok(TeamMode::Roles),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The teams players of a guild have joined
+#[derive(Clone)]
+pub struct Teams {
+    members: HashMap<UserId, TeamMember>,
+}
+
+// A player on a team
+#[derive(Clone)]
+struct TeamMember {
+    team: String,
+    // The player's name when they joined, for listing the team without mentioning anyone
+    name: String,
+}
+
+impl Default for Teams {
+    fn default() -> Teams {
+        Teams::new()
+    }
+}
+
+impl Teams {
+    pub fn new() -> Teams {
+        Teams {
+            members: HashMap::new(),
+        }
+    }
+
+    /// Puts a user on a team, taking them off any team they were on before
+    ///
+    /// Team names ignore case, so joining "red" joins an existing "Red" team.
+    /// Returns the name of the team as it is spelled on the list, or why the name can't be used.
+    /// Names are shown wherever the team's players are, so they can't mention anyone.
+    pub fn join(&mut self, user: &User, team: &str) -> Result<String, String> {
+        let team = team.trim();
+        if team.chars().count() > MAX_TEAM_NAME_LENGTH {
+            return Err(format!("Team names can be at most {} characters", MAX_TEAM_NAME_LENGTH));
+        }
+        if team.contains('@') || team.contains('<') {
+            return Err(String::from("Team names can't contain mentions or @"));
+        }
+
+        let name = self.members
+            .values()
+            .map(|member| &member.team)
+            .find(|existing| existing.eq_ignore_ascii_case(team))
+            .cloned()
+            .unwrap_or_else(|| team.to_string());
+
+        self.members.insert(user.id, TeamMember { team: name.clone(), name: user.name.clone() });
+        Ok(name)
+    }
+
+    /// Takes a user off their team, returning the team they were on
+    pub fn leave(&mut self, user: UserId) -> Option<String> {
+        self.members.remove(&user).map(|member| member.team)
+    }
+
+    /// Gets the team a user is on
+    pub fn team_of(&self, user: UserId) -> Option<&str> {
+        self.members.get(&user).map(|member| member.team.as_str())
+    }
+
+    /// Output every team and its members as a String
+    ///
+    /// `prefix` is the prefix commands start with in the guild, for telling players how to join.
+    pub fn output_teams(&self, prefix: &str) -> String {
+        // Sort by team and name so the list is stable
+        let mut teams: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for member in self.members.values() {
+            teams.entry(member.team.as_str()).or_default().push(member.name.as_str());
+        }
+
+        if teams.is_empty() {
+            return format!("Nobody has joined a team. Use {}tteam join <name> to make one.\n", prefix);
+        }
+
+        let mut output = String::from("Teams:\n");
+        for (team, mut members) in teams {
+            members.sort();
+            let s = format!("{} - {}\n", team, members.join(", "));
+            output.push_str(&s);
+        }
+
+        output
+    }
+}
+
+/// Gets the team named by the message author's first role starting with "Team"
+///
+/// Only works for messages in guilds the bot has cached.
+pub fn team_from_roles(message: &Message) -> Option<String> {
+    let mut roles = message.member()?.roles()?;
+    // Roles are checked from the highest down, so a player's most important team wins
+    roles.sort_by_key(|role| Reverse(role.position));
+
+    roles
+        .into_iter()
+        .find(|role| role.name.to_lowercase().starts_with(TEAM_ROLE_PREFIX))
+        .map(|role| role.name)
+}
@@ -0,0 +1,59 @@
+use serenity::model::id::ChannelId;
+use serenity::prelude::Mutex;
+use typemap::ShareMap;
+use std::sync::Arc;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Duration;
+
+use trivia::TriviaManager;
+
+/// The things a QuestionTimer can be counting down to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimerEvent {
+    /// The time limit for answering the question has run out
+    TimeUp,
+    /// The next automatic hint is due
+    Hint,
+    /// The game has been paused for longer than it is allowed to be
+    PauseLimit,
+}
+
+/// A countdown for a single question
+///
+/// When the countdown expires, the TriviaManager is told which event is due for the question it
+/// was started for. Dropping or cancelling the timer stops the countdown.
+pub struct QuestionTimer {
+    cancel: Sender<()>,
+}
+
+impl QuestionTimer {
+    /// Starts a countdown to `event` for the given question in the given channel
+    ///
+    /// `data` is the bot's shared data map which holds the TriviaManager. It is only locked once
+    /// the countdown expires.
+    pub fn start(data: Arc<Mutex<ShareMap>>,
+                 channel: ChannelId,
+                 question_number: usize,
+                 limit: Duration,
+                 event: TimerEvent) -> QuestionTimer {
+        let (cancel, cancelled) = mpsc::channel();
+
+        thread::spawn(move || {
+            // Any message, or the sender being dropped, cancels the timer
+            if let Err(RecvTimeoutError::Timeout) = cancelled.recv_timeout(limit) {
+                let mut data = data.lock();
+                if let Some(trivia_manager) = data.get_mut::<TriviaManager>() {
+                    trivia_manager.on_timer(channel, question_number, event);
+                }
+            }
+        });
+
+        QuestionTimer { cancel }
+    }
+
+    /// Stops the countdown before it expires
+    pub fn cancel(self) {
+        let _ = self.cancel.send(());
+    }
+}
@@ -1,247 +1,409 @@
 use serenity::model::channel::Message;
-use serenity::model::id::{ ChannelId, UserId };
+use serenity::model::id::{ChannelId, GuildId, UserId};
 use serenity::model::user::User;
-use typemap::Key;
+use serenity::prelude::Mutex;
+use typemap::{Key, ShareMap};
 use std::collections::HashMap;
 use std::fmt::Display;
+use std::sync::Arc;
+use std::thread;
 
-use db;
+use access::{AccessControl, Action, GuildRules};
+use cache::{Categories, QuestionCache};
+use config::{self, Config, Settings};
+use error::Result;
+use leaderboard::{Leaderboard, Period};
 use optionset::OptionSet;
 use questionset::QuestionSet;
-use scores::Scores;
+use session::TriviaSession;
+use skip::SkipThreshold;
+use teams::Teams;
+use timer::TimerEvent;
 
-/// The TriviaManager holds the current gamestate
+/// The TriviaManager holds every running game, keyed by the channel it is played in
 pub struct TriviaManager {
-    pub running: bool,
-    question_set: Option<QuestionSet>,
-    channel: Option<ChannelId>,
-    scores: Option<Scores>,
-    skips: u32,
-    user_answered_list: HashMap<UserId, bool>,
-    user_skipped_list: HashMap<UserId, bool>,
+    sessions: HashMap<ChannelId, TriviaSession>,
+    // The games still waiting for their questions, by channel
+    starting: HashMap<ChannelId, PendingStart>,
+    // The id given to the next game which has to wait for its questions
+    next_start_id: u64,
+    leaderboard: Leaderboard,
+    questions: Arc<QuestionCache>,
+    teams: HashMap<GuildId, Teams>,
+    access: AccessControl,
+    config: Config,
+    data: Arc<Mutex<ShareMap>>,
+}
+
+// A game waiting for its questions
+struct PendingStart {
+    // Tells the game apart from any started in the same channel after it was stopped
+    id: u64,
+    started_by: UserId,
 }
 
 impl Key for TriviaManager {
     type Value = TriviaManager;
 }
 
-/// This object keeps track of the current gamestate
+/// This object routes commands and answers to the game running in their channel
 impl TriviaManager {
-    /// Generates a new trivia manager. Channel and running are unset by default.
-    pub fn new() -> TriviaManager {
+    /// Generates a new trivia manager with no running games.
+    ///
+    /// `data` is the client's shared data map the manager will be stored in. Games use it to
+    /// get back to the manager from their question timers. Final scores of every finished game
+    /// are recorded on `leaderboard`, and every game's questions come from `questions`.
+    /// `access` decides who can control games, and `config` holds the settings games are
+    /// played with.
+    pub fn new(data: Arc<Mutex<ShareMap>>,
+               leaderboard: Leaderboard,
+               questions: Arc<QuestionCache>,
+               access: AccessControl,
+               config: Config) -> TriviaManager {
         TriviaManager {
-            running: false,
-            question_set: None,
-            channel: None,
-            scores: None,
-            skips: 0,
-            user_answered_list: HashMap::new(),
-            user_skipped_list: HashMap::new(),
+            sessions: HashMap::new(),
+            starting: HashMap::new(),
+            next_start_id: 0,
+            leaderboard,
+            questions,
+            teams: HashMap::new(),
+            access,
+            config,
+            data,
         }
     }
 
-    /// Starts the trivia bot
+    /// Starts a game in the channel given by the OptionSet
     ///
-    /// This function sets the `channel` member, which is used for sending messages during a game
-    pub fn start(&mut self, optionset: &OptionSet) {
-        if !self.running {
-            //Configure the trivia manager
-            self.running = true;
-            self.question_set = Some(db::get_question_set(&optionset));
-            self.channel = Some(optionset.channel);
-            self.scores = Some(Scores::new());
-
-            //Tell the user we've started and ask a question
-            self.say("Trivia Starting");
-            self.ask_question();
-        } else {
-            self.say("Trivia is already running");
-        }
-    }
-
-    /// Stops the trivia bot
-    pub fn stop(&mut self) {
-        if self.running {
-            self.print_scores();
-            self.say("Trivia Stopping");
-            self.running = false;
-            self.question_set = None;
-            self.channel = None;
-            self.scores = None;
-        } else {
-            self.say("Trivia is not running");
-        }
-    }
-
-    /// Skips the current question on the triviabot
-    pub fn vote_skip(&mut self, message: &Message) {
-        if self.running {
-            // Check if the user has already skipped
-            // If so, exit early and say nothing
-            if self.has_skipped(&message.author) {
-                return;
+    /// Games in other channels are unaffected. If the questions for the game are ready it starts
+    /// straight away, otherwise they're pulled on another thread and the game starts once they
+    /// arrive. If the game can't be started, no game is left running in the channel and the
+    /// error is returned for the caller to report, or posted if it happens later.
+    pub fn start(&mut self, optionset: &OptionSet) -> Result<()> {
+        if self.is_running(optionset.channel) || self.starting.contains_key(&optionset.channel) {
+            TriviaManager::say(optionset.channel, "Trivia is already running");
+            return Ok(());
+        }
+
+        match self.questions.take(optionset) {
+            Some(question_set) => {
+                QuestionCache::refill(&self.questions, optionset);
+                self.begin(optionset, question_set)
+            }
+            None => {
+                self.fetch_questions(optionset);
+                Ok(())
             }
-            self.skips += 1;
-            if self.skips >= 3 {
-                self.say("Skipping question.");
-
-                self.next_question();
-                self.ask_question();
-            } else {
-                self.say(format!("{} voted to skip. **Votes Needed: {}/3**", message.author.name, self.skips).as_str());
+        }
+    }
+
+    /// Starts a game whose questions have been pulled
+    ///
+    /// `start_id` is the id the game was given while it waited. Does nothing if the game was
+    /// stopped while its questions were being pulled, even if another game has been started in
+    /// the channel since.
+    pub fn on_questions(&mut self, start_id: u64, optionset: &OptionSet, question_set: Result<QuestionSet>) {
+        match self.starting.get(&optionset.channel) {
+            Some(pending) if pending.id == start_id => {}
+            _ => return,
+        }
+        self.starting.remove(&optionset.channel);
+
+        if let Err(why) = question_set.and_then(|question_set| self.begin(optionset, question_set)) {
+            TriviaManager::say(optionset.channel, format!("Couldn't start trivia: {}", why));
+        }
+    }
+
+    /// Stops the game running in the given channel
+    pub fn stop(&mut self, channel: ChannelId) {
+        if self.starting.remove(&channel).is_some() {
+            TriviaManager::say(channel, "Trivia won't start after all");
+            return;
+        }
+
+        match self.sessions.remove(&channel) {
+            Some(mut session) => {
+                session.stop();
+                self.record(&session);
             }
-        } else {
-            self.say("Can't skip because trivia is not running");
+            None => TriviaManager::say(channel, "Trivia is not running"),
+        }
+    }
+
+    /// Votes to skip the current question of the game running in the message's channel
+    pub fn vote_skip(&mut self, message: &Message) {
+        match self.sessions.get_mut(&message.channel_id) {
+            Some(session) => session.vote_skip(message),
+            None => TriviaManager::say(message.channel_id, "Can't skip because trivia is not running"),
         }
+        self.remove_finished(message.channel_id);
     }
 
     /// Method which runs whenever a new message is recieved.
     ///
-    /// If the triviabot is running, the text is checked to see if it is an answer
+    /// If a game is running in the message's channel, the message is passed on to it as a
+    /// potential answer
     pub fn on_message(&mut self, message: Message) {
-        if self.running && TriviaManager::valid_letter(&message) {
-
-            //Check if the answer is correct
-            let correct = self.check_answer(message.content.as_str());
-            //Check if this is the users first guess
-            let has_answered = self.has_answered(&message.author);
-            //If the answer is correct AND it is the user's first guess, they got the question
-            //right
-            if correct && !has_answered {
-                //Congradulate the user
-                self.say(
-                    format!("{} got the correct answer", &message.author.name));
-
-                //Increase the user's score by 1
-                self.scores
-                    .as_mut()
-                    .expect("Error getting scores in on_message()")
-                    .increase_score(message.author, 1);
-
-                self.next_question();
-                self.ask_question();
-            }
+        let channel = message.channel_id;
+        if let Some(session) = self.sessions.get_mut(&channel) {
+            session.on_message(message);
+        }
+        self.remove_finished(channel);
+    }
+
+    /// Skips the current question of the game running in the message's channel without a vote
+    pub fn force_skip(&mut self, message: &Message) {
+        match self.sessions.get_mut(&message.channel_id) {
+            Some(session) => session.force_skip(message),
+            None => TriviaManager::say(message.channel_id, "Can't skip because trivia is not running"),
+        }
+        self.remove_finished(message.channel_id);
+    }
+
+    /// Pauses the game running in the message's channel
+    pub fn pause(&mut self, message: &Message) {
+        let limit = self.config.pause_limit();
+        match self.sessions.get_mut(&message.channel_id) {
+            Some(session) => session.pause(limit),
+            None => TriviaManager::say(message.channel_id, "Can't pause because trivia is not running"),
+        }
+    }
+
+    /// Resumes the paused game in the message's channel
+    pub fn resume(&mut self, message: &Message) {
+        match self.sessions.get_mut(&message.channel_id) {
+            Some(session) => session.resume(),
+            None => TriviaManager::say(message.channel_id, "Can't resume because trivia is not running"),
         }
     }
 
-    fn next_question(&mut self) {
-        self.question_set
-            .as_mut()
-            .expect("Error getting questionset")
-            .next_question();
-        
-        self.user_answered_list.clear();
-        self.user_skipped_list.clear();
+    /// Gives a hint for the current question of the game running in the message's channel
+    pub fn hint(&mut self, message: &Message) {
+        match self.sessions.get_mut(&message.channel_id) {
+            Some(session) => session.give_hint(),
+            None => TriviaManager::say(message.channel_id, "Can't give a hint because trivia is not running"),
+        }
     }
 
-    /// Sets the channel where the triviabot will send it's messages
-    pub fn set_channel(&mut self, message: &Message) {
-        self.channel = Some(message.channel_id);
+    /// Posts the scores so far of the game running in the message's channel
+    pub fn standings(&self, message: &Message) {
+        match self.sessions.get(&message.channel_id) {
+            Some(session) => session.print_standings(),
+            None => TriviaManager::say(message.channel_id, "Can't show scores because trivia is not running"),
+        }
     }
 
-    fn valid_letter(message: &Message) -> bool {
-        let message = message.content.to_lowercase();
-        if message == "a" ||
-            message == "b" ||
-            message == "c" ||
-            message == "d" {
-                true
-            } else {
+    /// Called by a question timer once its countdown has run out
+    pub fn on_timer(&mut self, channel: ChannelId, question_number: usize, event: TimerEvent) {
+        if let Some(session) = self.sessions.get_mut(&channel) {
+            match event {
+                TimerEvent::TimeUp => session.time_up(question_number),
+                TimerEvent::Hint => session.auto_hint(question_number),
+                TimerEvent::PauseLimit => session.pause_expired(question_number),
+            }
+        }
+        self.remove_finished(channel);
+    }
+
+    /// Checks if the author of a message may take an action in the message's channel
+    ///
+    /// The author is told why if they can't.
+    pub fn allowed(&self, message: &Message, action: Action) -> bool {
+        let starter = self.sessions
+            .get(&message.channel_id)
+            .map(|session| session.started_by())
+            .or_else(|| self.starting.get(&message.channel_id).map(|pending| pending.started_by));
+        // Without a game there's nothing to control, and the command itself says so
+        if action.controls_game() && starter.is_none() {
+            return true;
+        }
+
+        match self.access.check(message, action, starter) {
+            Ok(()) => true,
+            Err(reason) => {
+                TriviaManager::say(message.channel_id, reason);
                 false
             }
+        }
     }
-    // Prints out the scorelist to the current channel
-    fn print_scores(&self) {
-        let scores = self.scores
-                        .as_ref()
-                        .expect("Error getting scores")
-                        .output_scores();
-        self.say(scores);
-    }
-
-    // Checks AND sets if a user has answered the question
-    // Returns true if the user has already answered, and false if they haven't.
-    // After this function is run, the passed user will not be able to answer a question again
-    // until the answered list is cleared
-    fn has_answered(&mut self, user: &User) -> bool {
-        //Mark that the given user has attempted to answer the question
-        let answered = self.user_answered_list.insert(user.id, true);
-
-        //If answered is a Some value, the user already answered. Return true.
-        //If the value is None, this is the users first answer. Return false, the user HASN'T
-        //answered yet
-        match answered {
-            Some(_) => true,
-            None => false,
+
+    /// Gets the rules deciding who can control games in a guild
+    pub fn rules(&self, guild: GuildId) -> GuildRules {
+        self.access.rules(guild)
+    }
+
+    /// Changes the rules deciding who can control games in a guild
+    pub fn set_rules(&mut self, guild: GuildId, rules: GuildRules) {
+        if let Err(why) = self.access.set_rules(guild, rules) {
+            error!("saving permission rules failed guild={} error={:?}", guild.0, why);
         }
+    }
 
-        //After the execution of this function, the user will be marked as having attempted to
-        //answer the question
+    /// Gets the settings games in a guild are played with, or the defaults outside of a guild
+    pub fn settings(&self, guild: Option<GuildId>) -> Settings {
+        self.config.settings(guild)
     }
 
-    //Checks AND sets if a user has skipped the current question
-    // Returns true if the user has already voted to skip, and false if they haven't.
-    // After this functionis run, the passed user will not be able to skip the current question
-    // again until the user_skipped_list has been cleared
-    fn has_skipped(&mut self, user: &User) -> bool {
-        let skipped = self.user_skipped_list.insert(user.id, true);
+    /// Gets the command prefix a guild has chosen, if it has one
+    pub fn guild_prefix(&self, guild: GuildId) -> Option<String> {
+        self.config.guild_prefix(guild)
+    }
 
-        match skipped {
-            Some(_) => true,
-            None    => false,
+    /// Changes one of a guild's settings, returning why the value can't be used if it's no good
+    pub fn set_setting(&mut self, guild: GuildId, key: &str, value: &str) -> ::std::result::Result<(), String> {
+        self.config.set(guild, key, value)
+    }
+
+    /// Puts one of a guild's settings back to the default
+    pub fn reset_setting(&mut self, guild: GuildId, key: &str) -> ::std::result::Result<(), String> {
+        self.config.reset(guild, key)
+    }
+
+    /// Outputs a guild's settings as a String, marking the ones it has changed
+    pub fn output_settings(&self, guild: GuildId) -> String {
+        let settings = self.config.settings(Some(guild));
+        let mut output = String::from("Settings:\n");
+        for key in config::KEYS.iter() {
+            let value = match *key {
+                "prefix" => settings.prefix.clone(),
+                "questions" => settings.questions.to_string(),
+                "difficulty" if settings.difficulty.is_empty() => String::from("any"),
+                "difficulty" => settings.difficulty.clone(),
+                "time" => format!("{}s", settings.time_limit.map_or(0, |limit| limit.as_secs())),
+                "skip" => match settings.skip_threshold {
+                    SkipThreshold::Votes(votes) => votes.to_string(),
+                    SkipThreshold::Percent(percent) => format!("{}%", percent),
+                },
+                _ => continue,
+            };
+            let marker = if self.config.is_overridden(guild, key) { "" } else { " (default)" };
+            let s = format!("{} = {}{}\n", key, value, marker);
+            output.push_str(&s);
         }
 
+        output
+    }
+
+    /// Puts a user on a team in the given guild, returning the team's name as it is listed
+    ///
+    /// Games already running in the guild pick up the change straight away. Returns why the
+    /// name can't be used if it's no good.
+    pub fn join_team(&mut self, guild: GuildId, user: &User, team: &str) -> ::std::result::Result<String, String> {
+        let name = self.teams.entry(guild).or_insert_with(Teams::new).join(user, team)?;
+        self.update_teams(guild);
+        Ok(name)
+    }
+
+    /// Takes a user off their team in the given guild, returning the team they were on
+    pub fn leave_team(&mut self, guild: GuildId, user: UserId) -> Option<String> {
+        let left = self.teams.get_mut(&guild).and_then(|teams| teams.leave(user));
+        self.update_teams(guild);
+        left
+    }
+
+    /// Outputs the teams of a guild as a String
+    pub fn output_teams(&self, guild: GuildId) -> String {
+        self.teams_of(guild).output_teams(&self.settings(Some(guild)).prefix)
+    }
+
+    /// Outputs the leaderboard of a guild over the given period as a String
+    pub fn leaderboard(&self, guild: GuildId, period: Period) -> String {
+        self.leaderboard.output_leaderboard(guild, period)
+    }
+
+    /// Lists the categories of the question source, without waiting for the list to be pulled
+    pub fn categories(&self) -> Categories {
+        QuestionCache::categories(&self.questions)
+    }
+
+    /// Checks if a game is running in the given channel
+    pub fn is_running(&self, channel: ChannelId) -> bool {
+        self.sessions.contains_key(&channel)
+    }
+
+    // Gets a copy of the teams of a guild
+    fn teams_of(&self, guild: GuildId) -> Teams {
+        self.teams.get(&guild).cloned().unwrap_or_else(Teams::new)
     }
 
-    // Sends a message to the active trivia channel with the current question
-    // When no more questions are available, this method calls the stop() method
-    fn ask_question(&mut self) {
-        // If question is false, there was no question to ask
-        let question = match self.question_set
-                                .as_ref()
-                                .expect("Error getting questionset in ask_question()")
-                                .get_current_question() 
-        {
-            Some(q) => {
-                self.say(format!("```Question: {}\n{}```", &q.prompt, &q.answer_prompt));
-                println!("Answer: {}", q.answer);
-                true
+    // Hands the current teams of a guild to every game running in it
+    fn update_teams(&mut self, guild: GuildId) {
+        let teams = self.teams_of(guild);
+        for session in self.sessions.values_mut() {
+            if session.guild() == Some(guild) {
+                session.set_teams(teams.clone());
             }
-            None => {
-                self.say("Out of questions");
-                false
+        }
+    }
+
+    // Starts a game with the given questions
+    fn begin(&mut self, optionset: &OptionSet, question_set: QuestionSet) -> Result<()> {
+        let teams = match optionset.guild {
+            Some(guild) => self.teams_of(guild),
+            None => Teams::new(),
+        };
+        let session = TriviaSession::start(optionset, question_set, teams, self.data.clone())?;
+        self.sessions.insert(optionset.channel, session);
+
+        Ok(())
+    }
+
+    // Pulls the questions for a game on another thread, so other channels aren't held up while
+    // the source answers, then starts the game and tops up its pool
+    fn fetch_questions(&mut self, optionset: &OptionSet) {
+        self.next_start_id += 1;
+        let start_id = self.next_start_id;
+        self.starting.insert(optionset.channel, PendingStart { id: start_id, started_by: optionset.started_by });
+        TriviaManager::say(optionset.channel, "Fetching questions...");
+
+        let questions = self.questions.clone();
+        let data = self.data.clone();
+        let optionset = optionset.clone();
+        thread::spawn(move || {
+            let question_set = questions.fetch(&optionset);
+            QuestionCache::refill(&questions, &optionset);
+
+            let mut data = data.lock();
+            if let Some(trivia_manager) = data.get_mut::<TriviaManager>() {
+                trivia_manager.on_questions(start_id, &optionset, question_set);
             }
+        });
+    }
+
+    // Drops the session for a channel once its game has ended on its own
+    // (eg. by running out of questions)
+    fn remove_finished(&mut self, channel: ChannelId) {
+        let finished = match self.sessions.get(&channel) {
+            Some(session) => !session.running,
+            None => false,
         };
 
-        // Stop if we don't have any more questions to ask
-        if !question {
-            self.stop();
+        if finished {
+            if let Some(session) = self.sessions.remove(&channel) {
+                self.record(&session);
+            }
         }
     }
 
-    // Checks if a given string matches the current question's answer
-    fn check_answer(&mut self, message: &str) -> bool {
-        let question = self.question_set
-                            .as_ref()
-                            .expect("Error getting question set")
-                            .get_current_question();
+    // Adds the final scores of a finished game to the leaderboard
+    // Games outside of a guild (eg. in direct messages) aren't recorded
+    fn record(&mut self, session: &TriviaSession) {
+        if let Some(guild) = session.guild() {
+            let results = session.scores().results();
+            if results.is_empty() {
+                return;
+            }
 
-        match question {
-            Some(q) => {
-                //Check if the message is the same as the answer
-                message.to_lowercase() == q.answer_letter.to_lowercase()
+            if let Err(why) = self.leaderboard.record_game(guild, &results) {
+                error!("saving leaderboard failed guild={} error={:?}", guild.0, why);
             }
-            None => false,
         }
     }
 
-    // Sends a message to the currently set channel
-    // Accepts a String object
-    // If no channel exists, outputs an error message to the console
-    //
-    fn say<T: Display>(&self, message: T) {
-        let _ = self.channel
-            .expect("Tried using say without a channel set")
-            .say(format!("{}", message));
+    // Sends a message to a channel which has no running session
+    fn say<T: Display>(channel: ChannelId, message: T) {
+        if let Err(why) = channel.say(format!("{}", message)) {
+            error!("message send failed channel={} error={:?}", channel.0, why);
+        }
     }
 }